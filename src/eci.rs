@@ -0,0 +1,169 @@
+use std::convert::TryFrom;
+
+use encoding_rs::{Encoding, BIG5, EUC_KR, GB18030, ISO_8859_2, ISO_8859_3, ISO_8859_4, ISO_8859_5, ISO_8859_6,
+    ISO_8859_7, ISO_8859_8, ISO_8859_10, ISO_8859_13, ISO_8859_14, ISO_8859_15, ISO_8859_16, SHIFT_JIS,
+    WINDOWS_1250, WINDOWS_1251, WINDOWS_1252, WINDOWS_1256};
+
+// character sets that byte mode data can be converted to, announced to the reader by an ECI segment
+// source: https://en.wikipedia.org/wiki/Extended_Channel_Interpretation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Charset {
+    Iso8859_1,
+    Iso8859_2,
+    Iso8859_3,
+    Iso8859_4,
+    Iso8859_5,
+    Iso8859_6,
+    Iso8859_7,
+    Iso8859_8,
+    Iso8859_10,
+    Iso8859_13,
+    Iso8859_14,
+    Iso8859_15,
+    Iso8859_16,
+    ShiftJis,
+    Windows1250,
+    Windows1251,
+    Windows1252,
+    Windows1256,
+    Utf16Be,
+    #[default]
+    Utf8,
+    UsAscii,
+    Big5,
+    Gb18030,
+    EucKr,
+}
+
+impl Charset {
+    // ECI assignment number of the character set
+    pub fn eci_number(&self) -> u32 {
+        match self {
+            Charset::Iso8859_1   => 3,
+            Charset::Iso8859_2   => 4,
+            Charset::Iso8859_3   => 5,
+            Charset::Iso8859_4   => 6,
+            Charset::Iso8859_5   => 7,
+            Charset::Iso8859_6   => 8,
+            Charset::Iso8859_7   => 9,
+            Charset::Iso8859_8   => 10,
+            Charset::Iso8859_10  => 12,
+            Charset::Iso8859_13  => 15,
+            Charset::Iso8859_14  => 16,
+            Charset::Iso8859_15  => 17,
+            Charset::Iso8859_16  => 18,
+            Charset::ShiftJis    => 20,
+            Charset::Windows1250 => 21,
+            Charset::Windows1251 => 22,
+            Charset::Windows1252 => 23,
+            Charset::Windows1256 => 24,
+            Charset::Utf16Be     => 25,
+            Charset::Utf8        => 26,
+            Charset::UsAscii     => 27,
+            Charset::Big5        => 28,
+            Charset::Gb18030     => 29,
+            Charset::EucKr       => 30,
+        }
+    }
+
+    fn get_encoding(&self) -> Option<&'static Encoding> {
+        match self {
+            Charset::Iso8859_2   => Some(ISO_8859_2),
+            Charset::Iso8859_3   => Some(ISO_8859_3),
+            Charset::Iso8859_4   => Some(ISO_8859_4),
+            Charset::Iso8859_5   => Some(ISO_8859_5),
+            Charset::Iso8859_6   => Some(ISO_8859_6),
+            Charset::Iso8859_7   => Some(ISO_8859_7),
+            Charset::Iso8859_8   => Some(ISO_8859_8),
+            Charset::Iso8859_10  => Some(ISO_8859_10),
+            Charset::Iso8859_13  => Some(ISO_8859_13),
+            Charset::Iso8859_14  => Some(ISO_8859_14),
+            Charset::Iso8859_15  => Some(ISO_8859_15),
+            Charset::Iso8859_16  => Some(ISO_8859_16),
+            Charset::ShiftJis    => Some(SHIFT_JIS),
+            Charset::Windows1250 => Some(WINDOWS_1250),
+            Charset::Windows1251 => Some(WINDOWS_1251),
+            Charset::Windows1252 => Some(WINDOWS_1252),
+            Charset::Windows1256 => Some(WINDOWS_1256),
+            Charset::Big5        => Some(BIG5),
+            Charset::Gb18030     => Some(GB18030),
+            Charset::EucKr       => Some(EUC_KR),
+            _                    => None,
+        }
+    }
+
+    // the bytes of the text in this character set, None if a character can not be represented
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Charset::Utf8      => Some(text.as_bytes().to_vec()),
+            Charset::Utf16Be   => Some(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect()),
+            Charset::Iso8859_1 => text.chars().map(|c| u8::try_from(c).ok()).collect(),
+            Charset::UsAscii   => text.chars().map(|c| if c.is_ascii() { Some(c as u8) } else { None }).collect(),
+            _ => {
+                let (bytes, _, had_errors) = self.get_encoding()?.encode(text);
+                if had_errors { None } else { Some(bytes.into_owned()) }
+            },
+        }
+    }
+}
+
+// the assignment number as 8, 16 or 24 bit designator, the leading 1s announce the number of bytes
+// None for numbers above 999999, the largest one the 24 bit designator may hold
+pub fn encode_eci_designator(eci_number: u32) -> Option<String> {
+    match eci_number {
+        0..=127        => Some(format!("0{:07b}", eci_number)),
+        128..=16383    => Some(format!("10{:014b}", eci_number)),
+        16384..=999999 => Some(format!("110{:021b}", eci_number)),
+        _              => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::{ErrorCorrectionLevel, QRCode, QREncoding};
+
+    fn encodings(code: &QRCode) -> Vec<&QREncoding> {
+        code.segments().iter().map(|segment| segment.encoding()).collect()
+    }
+
+    // source: ISO/IEC 18004:2015 section 7.4.2.2, table 4
+    #[test]
+    fn designator_length_follows_the_assignment_number() {
+        assert_eq!(encode_eci_designator(0).unwrap(), "00000000");
+        assert_eq!(encode_eci_designator(127).unwrap(), "01111111");
+        assert_eq!(encode_eci_designator(128).unwrap(), "1000000010000000");
+        assert_eq!(encode_eci_designator(16383).unwrap(), "1011111111111111");
+        assert_eq!(encode_eci_designator(16384).unwrap(), "110000000100000000000000");
+        assert_eq!(encode_eci_designator(999999).unwrap(), "110011110100001000111111");
+        assert_eq!(encode_eci_designator(1_000_000), None);
+    }
+
+    #[test]
+    fn eci_header_is_only_added_for_non_ascii_byte_data() {
+        let level = ErrorCorrectionLevel::M;
+
+        let ascii = QRCode::new(String::from("hello, world"), level).unwrap();
+        assert!(!encodings(&ascii).contains(&&QREncoding::Eci(26)));
+
+        let kanji = QRCode::new(String::from("点茗"), level).unwrap();
+        assert_eq!(encodings(&kanji), [&QREncoding::Kanji]);
+
+        let utf8 = QRCode::new(String::from("grüße"), level).unwrap();
+        assert_eq!(encodings(&utf8), [&QREncoding::Eci(26), &QREncoding::Byte]);
+        assert_eq!(utf8.segments()[1].bytes(), "grüße".as_bytes());
+
+        let latin1 = QRCode::new_with_charset(String::from("grüße"), level, Charset::Iso8859_1).unwrap();
+        assert_eq!(encodings(&latin1), [&QREncoding::Eci(3), &QREncoding::Byte]);
+        assert_eq!(latin1.segments()[1].bytes(), b"gr\xFC\xDFe");
+    }
+
+    #[test]
+    fn characters_outside_of_the_charset_are_rejected() {
+        assert_eq!(Charset::Iso8859_1.encode("€"), None);
+        assert_eq!(Charset::UsAscii.encode("é"), None);
+
+        let result = QRCode::new_with_charset(String::from("5 €"), ErrorCorrectionLevel::M, Charset::Iso8859_1);
+        assert_eq!(result.err().as_deref(), Some("UnsupportedCharacter"));
+    }
+}
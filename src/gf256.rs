@@ -0,0 +1,224 @@
+use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign};
+
+// source: https://www.thonky.com/qr-code-tutorial/log-antilog-table
+
+// x^8 + x^4 + x^3 + x^2 + 1, the primitive polynomial of the QR code field
+pub const QR_PRIMITIVE_POLYNOMIAL: u16 = 0b1_0001_1101;
+
+// powers of the generator 2 and their logarithms for the field defined by a primitive polynomial of degree 8
+// the antilog table is twice as long, so the sum of two logarithms can be looked up without reducing it first
+pub struct GfTables {
+    exp: [u8; 510],
+    log: [u8; 256],
+}
+
+impl GfTables {
+    // fails to compile if the polynomial is not primitive, i.e. 2 does not generate all 255 nonzero elements
+    pub const fn new(primitive_polynomial: u16) -> Self {
+        assert!(primitive_polynomial >> 8 == 1, "The primitive polynomial must have degree 8!");
+
+        let mut exp = [0u8; 510];
+        let mut log = [0u8; 256];
+
+        let mut value: u16 = 1;
+        let mut i = 0;
+        while i < 255 {
+            assert!(i == 0 || value != 1, "The polynomial is not primitive!");
+
+            exp[i] = value as u8;
+            exp[i + 255] = value as u8;
+            log[value as usize] = i as u8;
+
+            value <<= 1;
+            if value > 255 { value ^= primitive_polynomial; }
+            i += 1;
+        }
+        assert!(value == 1, "The polynomial is not primitive!");
+
+        Self { exp, log }
+    }
+
+    // 2 raised to the power exp, usable in constant expressions
+    pub const fn alpha_pow(&self, exp: usize) -> u8 {
+        self.exp[exp % 255]
+    }
+
+    // product of two field elements, usable in constant expressions
+    pub const fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 { return 0; }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+}
+
+// element of GF(2^8) with the given primitive polynomial, addition and subtraction are xor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Gf<const POLY: u16>(pub u8);
+
+// the field used by the error correction of all QR code symbols
+pub type Gf256 = Gf<QR_PRIMITIVE_POLYNOMIAL>;
+
+impl<const POLY: u16> Gf<POLY> {
+    const TABLES: GfTables = GfTables::new(POLY);
+
+    pub const ZERO: Self = Gf(0);
+    pub const ONE: Self = Gf(1);
+
+    fn tables() -> &'static GfTables {
+        &Self::TABLES
+    }
+
+    // 2 raised to the power exp, 2 is the primitive element alpha of the field
+    pub fn alpha_pow(exp: usize) -> Self {
+        Gf(Self::tables().exp[exp % 255])
+    }
+
+    // the exponent of alpha that gives this element, zero has none
+    pub fn log(self) -> Option<usize> {
+        if self.0 == 0 { None } else { Some(Self::tables().log[self.0 as usize] as usize) }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    // multiplicative inverse, panics for zero like an integer division by zero
+    pub fn inv(self) -> Self {
+        let log = self.log().expect("Zero has no inverse in GF(256)!");
+        Gf(Self::tables().exp[255 - log])
+    }
+
+    pub fn pow(self, exp: u32) -> Self {
+        match self.log() {
+            Some(log) => Self::alpha_pow((log * (exp as usize % 255)) % 255),
+            None if exp == 0 => Self::ONE,
+            None => Self::ZERO,
+        }
+    }
+}
+
+impl<const POLY: u16> From<u8> for Gf<POLY> {
+    fn from(value: u8) -> Self {
+        Gf(value)
+    }
+}
+
+impl<const POLY: u16> From<Gf<POLY>> for u8 {
+    fn from(value: Gf<POLY>) -> Self {
+        value.0
+    }
+}
+
+impl<const POLY: u16> Add for Gf<POLY> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Self) -> Self {
+        Gf(self.0 ^ rhs.0)
+    }
+}
+
+impl<const POLY: u16> Sub for Gf<POLY> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Self) -> Self {
+        Gf(self.0 ^ rhs.0)
+    }
+}
+
+impl<const POLY: u16> Mul for Gf<POLY> {
+    type Output = Self;
+
+    // the logarithms are added, the antilog table covers their whole range
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Self) -> Self {
+        match (self.log(), rhs.log()) {
+            (Some(a), Some(b)) => Gf(Self::tables().exp[a + b]),
+            _ => Self::ZERO,
+        }
+    }
+}
+
+impl<const POLY: u16> Div for Gf<POLY> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self {
+        self * rhs.inv()
+    }
+}
+
+impl<const POLY: u16> AddAssign for Gf<POLY> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const POLY: u16> SubAssign for Gf<POLY> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const POLY: u16> MulAssign for Gf<POLY> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<const POLY: u16> DivAssign for Gf<POLY> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the expected values were computed by carry-less multiplication modulo x^8 + x^4 + x^3 + x^2 + 1
+    #[test]
+    fn arithmetic_matches_known_values() {
+        assert_eq!(Gf256::from(0x57) * Gf256::from(0x83), Gf(0x31));
+        assert_eq!(Gf256::from(0xFF) * Gf256::from(0xFF), Gf(0xE2));
+        assert_eq!(Gf256::from(0x80) * Gf256::from(0x02), Gf(0x1D));
+        assert_eq!(Gf256::from(0x57) * Gf256::ZERO, Gf256::ZERO);
+        assert_eq!(Gf256::from(0x57) / Gf256::from(0x83), Gf(0x8D));
+        assert_eq!(Gf256::from(0x02).inv(), Gf(0x8E));
+        assert_eq!(Gf256::from(0x53).inv(), Gf(0x8C));
+        assert_eq!(Gf256::from(0x53).pow(3), Gf(0xD0));
+        assert_eq!(Gf256::from(0x03).pow(100), Gf(0xA7));
+        assert_eq!(Gf256::ZERO.pow(0), Gf256::ONE);
+        assert_eq!(Gf256::ZERO.pow(5), Gf256::ZERO);
+
+        // source: https://www.thonky.com/qr-code-tutorial/log-antilog-table
+        assert_eq!(Gf256::alpha_pow(8), Gf(0x1D));
+        assert_eq!(Gf256::alpha_pow(25), Gf(0x03));
+        assert_eq!(Gf256::alpha_pow(255), Gf256::ONE);
+        assert_eq!(Gf256::from(0x03).log(), Some(25));
+        assert_eq!(Gf256::ZERO.log(), None);
+    }
+
+    #[test]
+    fn every_nonzero_element_has_an_inverse() {
+        for value in 1..=255 {
+            let x = Gf256::from(value);
+            assert_eq!(x.inv() * x, Gf256::ONE, "{:#04x}", value);
+            assert_eq!(x / x, Gf256::ONE);
+        }
+    }
+
+    #[test]
+    fn tables_usable_in_constant_expressions() {
+        const TABLES: GfTables = GfTables::new(QR_PRIMITIVE_POLYNOMIAL);
+        assert_eq!(TABLES.mul(0x57, 0x83), 0x31);
+        assert_eq!(TABLES.alpha_pow(8), 0x1D);
+    }
+
+    // x^8 + x^4 + x^3 + x + 1 of AES is irreducible, but 2 only generates 51 of its elements
+    #[test]
+    #[should_panic(expected = "The polynomial is not primitive!")]
+    fn non_primitive_polynomial_is_rejected() {
+        let _ = GfTables::new(0x11B);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::qrcode::GROUP_SEPARATOR;
+
+// source: https://www.gs1.org/standards/barcodes/application-identifiers
+
+#[derive(Debug, PartialEq)]
+pub enum Gs1Error {
+    Syntax,
+    UnknownAI(String),
+    InvalidLength(String),
+    InvalidCharacter(String),
+    InvalidCheckDigit(String),
+    InvalidDate(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum FieldKind {
+    // digits only, the last one is a GS1 mod 10 check digit if check_digit is set
+    Numeric { check_digit: bool },
+    // YYMMDD, the day may be 00 if only the month is known
+    Date,
+    // characters of GS1 character set 82
+    AlphaNumeric,
+}
+
+// ai prefix, length of the whole ai, kind of the field, minimum and maximum field length
+// the ais of the measurement groups like 310n end with a decimal point position that is matched by the length
+const APPLICATION_IDENTIFIERS: [(&str, usize, FieldKind, usize, usize); 57] = [
+    ("00",   2, FieldKind::Numeric { check_digit: true },  18, 18),
+    ("01",   2, FieldKind::Numeric { check_digit: true },  14, 14),
+    ("02",   2, FieldKind::Numeric { check_digit: true },  14, 14),
+    ("10",   2, FieldKind::AlphaNumeric,                    1, 20),
+    ("11",   2, FieldKind::Date,                            6,  6),
+    ("12",   2, FieldKind::Date,                            6,  6),
+    ("13",   2, FieldKind::Date,                            6,  6),
+    ("15",   2, FieldKind::Date,                            6,  6),
+    ("16",   2, FieldKind::Date,                            6,  6),
+    ("17",   2, FieldKind::Date,                            6,  6),
+    ("20",   2, FieldKind::Numeric { check_digit: false },  2,  2),
+    ("21",   2, FieldKind::AlphaNumeric,                    1, 20),
+    ("22",   2, FieldKind::AlphaNumeric,                    1, 20),
+    ("30",   2, FieldKind::Numeric { check_digit: false },  1,  8),
+    ("37",   2, FieldKind::Numeric { check_digit: false },  1,  8),
+    ("90",   2, FieldKind::AlphaNumeric,                    1, 30),
+    ("91",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("92",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("93",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("94",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("95",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("96",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("97",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("98",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("99",   2, FieldKind::AlphaNumeric,                    1, 90),
+    ("240",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("241",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("250",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("251",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("400",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("401",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("402",  3, FieldKind::Numeric { check_digit: true },  17, 17),
+    ("403",  3, FieldKind::AlphaNumeric,                    1, 30),
+    ("410",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("411",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("412",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("413",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("414",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("415",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("416",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("417",  3, FieldKind::Numeric { check_digit: true },  13, 13),
+    ("420",  3, FieldKind::AlphaNumeric,                    1, 20),
+    ("422",  3, FieldKind::Numeric { check_digit: false },  3,  3),
+    ("31",   4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("32",   4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("33",   4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("34",   4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("35",   4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("36",   4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("390",  4, FieldKind::Numeric { check_digit: false },  1, 15),
+    ("392",  4, FieldKind::Numeric { check_digit: false },  1, 15),
+    ("7003", 4, FieldKind::Numeric { check_digit: false }, 10, 10),
+    ("8004", 4, FieldKind::AlphaNumeric,                    1, 30),
+    ("8005", 4, FieldKind::Numeric { check_digit: false },  6,  6),
+    ("8017", 4, FieldKind::Numeric { check_digit: true },  18, 18),
+    ("8018", 4, FieldKind::Numeric { check_digit: true },  18, 18),
+    ("8020", 4, FieldKind::AlphaNumeric,                    1, 25),
+];
+
+// ais starting with these two digits have a predefined length and need no separator after them
+const PREDEFINED_LENGTH_PREFIXES: [&str; 22] = [
+    "00", "01", "02", "03", "04", "11", "12", "13", "14", "15", "16",
+    "17", "18", "19", "20", "31", "32", "33", "34", "35", "36", "41",
+];
+
+fn find_application_identifier(ai: &str) -> Option<&'static (&'static str, usize, FieldKind, usize, usize)> {
+    APPLICATION_IDENTIFIERS.iter().find(|entry| entry.1 == ai.len() && ai.starts_with(entry.0))
+}
+
+// GS1 character set 82, the characters allowed in alphanumeric fields
+fn is_cset82(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!\"%&'()*+,-./:;<=>?_".contains(c)
+}
+
+// GS1 mod 10 check: weights 3 and 1 alternate starting from the rightmost data digit
+fn is_valid_check_digit(digits: &str) -> bool {
+    let values: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    let (data, check) = values.split_at(values.len() - 1);
+
+    let sum: u32 = data.iter().rev().enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { digit * 3 } else { *digit })
+        .sum();
+
+    (10 - sum % 10) % 10 == check[0]
+}
+
+// the century is not part of the date, every year divisible by 4 is a leap year from 1901 to 2099
+fn is_valid_date(date: &str) -> bool {
+    let year: u32 = date[0..2].parse().unwrap_or(0);
+    let month: u32 = date[2..4].parse().unwrap_or(0);
+    let day: u32 = date[4..6].parse().unwrap_or(99);
+
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11              => 30,
+        2 if year.is_multiple_of(4) => 29,
+        2                           => 28,
+        _                           => { return false; }
+    };
+
+    day <= days_in_month
+}
+
+fn validate_field(ai: &str, value: &str) -> Result<(), Gs1Error> {
+    let (_, _, kind, min_len, max_len) = find_application_identifier(ai)
+        .ok_or_else(|| Gs1Error::UnknownAI(ai.to_owned()))?;
+
+    if value.len() < *min_len || value.len() > *max_len {
+        return Err(Gs1Error::InvalidLength(ai.to_owned()));
+    }
+
+    match kind {
+        FieldKind::Numeric { check_digit } => {
+            if !value.chars().all(|c| c.is_ascii_digit()) {
+                return Err(Gs1Error::InvalidCharacter(ai.to_owned()));
+            }
+            if *check_digit && !is_valid_check_digit(value) {
+                return Err(Gs1Error::InvalidCheckDigit(ai.to_owned()));
+            }
+        },
+        FieldKind::Date => {
+            if !value.chars().all(|c| c.is_ascii_digit()) {
+                return Err(Gs1Error::InvalidCharacter(ai.to_owned()));
+            }
+            if !is_valid_date(value) {
+                return Err(Gs1Error::InvalidDate(ai.to_owned()));
+            }
+        },
+        FieldKind::AlphaNumeric => {
+            if !value.chars().all(is_cset82) {
+                return Err(Gs1Error::InvalidCharacter(ai.to_owned()));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+// parses a bracketed element string like (01)09501101530003(17)140704(10)AB-123 into the encoded form
+// ais and values are concatenated, variable length fields are terminated by a group separator unless they are last
+// a bracket inside of a value is written as \( so that it does not start the next ai, closing brackets need no escape
+pub fn parse_element_string(element_string: &str) -> Result<String, Gs1Error> {
+    let mut fields = Vec::new();
+    let mut rest = element_string;
+
+    while !rest.is_empty() {
+        if !rest.starts_with('(') { return Err(Gs1Error::Syntax); }
+
+        let ai_end = rest.find(')').ok_or(Gs1Error::Syntax)?;
+        let ai = &rest[1..ai_end];
+        if ai.len() < 2 || ai.len() > 4 || !ai.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Gs1Error::Syntax);
+        }
+
+        rest = &rest[ai_end + 1..];
+        let mut value = String::new();
+        let mut chars = rest.char_indices().peekable();
+        let value_end = loop {
+            match chars.next() {
+                Some((_, '\\')) if chars.peek().map(|&(_, c)| c) == Some('(') => { chars.next(); value.push('('); },
+                Some((i, '(')) => { break i; },
+                Some((_, c)) => { value.push(c); },
+                None => { break rest.len(); },
+            }
+        };
+        rest = &rest[value_end..];
+
+        validate_field(ai, &value)?;
+        fields.push((ai, value));
+    }
+
+    if fields.is_empty() { return Err(Gs1Error::Syntax); }
+
+    let mut encoded = String::new();
+    for (i, (ai, value)) in fields.iter().enumerate() {
+        encoded.push_str(ai);
+        encoded.push_str(value);
+
+        let is_last = i == fields.len() - 1;
+        if !is_last && !PREDEFINED_LENGTH_PREFIXES.contains(&&ai[..2]) {
+            encoded.push(GROUP_SEPARATOR);
+        }
+    }
+
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::{ErrorCorrectionLevel, QRCode, QREncoding};
+
+    // source: https://www.gs1.org/services/how-calculate-check-digit-manually
+    #[test]
+    fn check_digits_are_verified() {
+        assert!(is_valid_check_digit("09501101530003"));
+        assert!(is_valid_check_digit("106141411234567897"));
+        assert!(is_valid_check_digit("5412345000013"));
+        assert!(!is_valid_check_digit("09501101530004"));
+
+        assert_eq!(parse_element_string("(01)09501101530004"), Err(Gs1Error::InvalidCheckDigit(String::from("01"))));
+        assert_eq!(parse_element_string("(414)5412345000014"), Err(Gs1Error::InvalidCheckDigit(String::from("414"))));
+    }
+
+    #[test]
+    fn unknown_ais_are_rejected() {
+        assert_eq!(parse_element_string("(23)12"), Err(Gs1Error::UnknownAI(String::from("23"))));
+        assert_eq!(parse_element_string("(418)5412345000013"), Err(Gs1Error::UnknownAI(String::from("418"))));
+        assert_eq!(parse_element_string("(419)5412345000013"), Err(Gs1Error::UnknownAI(String::from("419"))));
+        assert_eq!(parse_element_string("(417)5412345000013"), Ok(String::from("4175412345000013")));
+        assert_eq!(parse_element_string("(1)2"), Err(Gs1Error::Syntax));
+        assert_eq!(parse_element_string(""), Err(Gs1Error::Syntax));
+    }
+
+    #[test]
+    fn field_lengths_are_checked() {
+        assert_eq!(parse_element_string("(01)0950110153000"), Err(Gs1Error::InvalidLength(String::from("01"))));
+        assert_eq!(parse_element_string("(01)095011015300030"), Err(Gs1Error::InvalidLength(String::from("01"))));
+        assert_eq!(parse_element_string("(10)"), Err(Gs1Error::InvalidLength(String::from("10"))));
+        assert_eq!(parse_element_string("(10)ABCDEFGHIJKLMNOPQRSTU"), Err(Gs1Error::InvalidLength(String::from("10"))));
+        assert_eq!(parse_element_string("(10)ABCDEFGHIJKLMNOPQRST"), Ok(String::from("10ABCDEFGHIJKLMNOPQRST")));
+        assert_eq!(parse_element_string("(3103)001250"), Ok(String::from("3103001250")));
+        assert_eq!(parse_element_string("(3103)00125"), Err(Gs1Error::InvalidLength(String::from("3103"))));
+    }
+
+    #[test]
+    fn dates_are_checked_per_month() {
+        assert!(is_valid_date("240229"));
+        assert!(is_valid_date("240430"));
+        assert!(is_valid_date("241200"));
+        assert!(!is_valid_date("300229"));
+        assert!(!is_valid_date("310231"));
+        assert!(!is_valid_date("240431"));
+        assert!(!is_valid_date("241301"));
+        assert!(!is_valid_date("240001"));
+
+        assert_eq!(parse_element_string("(17)310231"), Err(Gs1Error::InvalidDate(String::from("17"))));
+    }
+
+    #[test]
+    fn separators_follow_variable_length_fields_only() {
+        let encoded = parse_element_string("(01)09501101530003(10)AB-123(17)140704(21)XYZ").unwrap();
+        assert_eq!(encoded, "010950110153000310AB-123\u{1D}1714070421XYZ");
+
+        let encoded = parse_element_string("(10)AB-123(21)XYZ").unwrap();
+        assert_eq!(encoded, "10AB-123\u{1D}21XYZ");
+
+        let encoded = parse_element_string("(01)09501101530003(17)140704").unwrap();
+        assert_eq!(encoded, "010950110153000317140704");
+    }
+
+    #[test]
+    fn escaped_brackets_inside_of_values() {
+        assert_eq!(parse_element_string("(10)A\\(1)B(21)C)"), Ok(String::from("10A(1)B\u{1D}21C)")));
+        assert_eq!(parse_element_string("(10)A(1)B"), Err(Gs1Error::Syntax));
+    }
+
+    #[test]
+    fn percent_is_escaped_in_alphanumeric_mode() {
+        let code = QRCode::new_gs1("(10)ABC%DEF(21)GHIJKL", ErrorCorrectionLevel::M).unwrap();
+
+        let encodings: Vec<&QREncoding> = code.segments().iter().map(|segment| segment.encoding()).collect();
+        assert_eq!(encodings, [&QREncoding::Fnc1First, &QREncoding::AlphaNumeric]);
+        assert_eq!(code.segments()[1].data(), "10ABC%%DEF%21GHIJKL");
+    }
+}
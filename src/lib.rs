@@ -0,0 +1,12 @@
+pub mod qrcode;
+pub mod eci;
+pub mod gs1;
+pub mod gf256;
+pub mod poly;
+pub mod solomon_reed;
+pub mod matrix;
+pub mod segment;
+pub mod micro;
+pub mod rmqr;
+pub mod render;
+pub mod logo;
@@ -0,0 +1,267 @@
+use crate::matrix::QRMatrix;
+use crate::qrcode::{QRCode, QRCodeError, ErrorCorrectionLevel, get_err_metadata, interleaved_block_indices};
+
+// share of the correctable codewords of every block a logo may use, the rest is left for print defects and dirt
+pub const LOGO_SAFETY_MARGIN: f64 = 0.5;
+
+// error correction codewords of the smallest symbols that only protect against misdecodes and correct nothing
+// source: ISO/IEC 18004:2015 table 9
+const MISDECODE_PROTECTION_CODEWORDS: [(usize, ErrorCorrectionLevel, usize); 5] = [
+    (1, ErrorCorrectionLevel::L, 3),
+    (1, ErrorCorrectionLevel::M, 2),
+    (1, ErrorCorrectionLevel::Q, 1),
+    (2, ErrorCorrectionLevel::L, 2),
+    (3, ErrorCorrectionLevel::L, 1),
+];
+
+const LEVELS: [ErrorCorrectionLevel; 4] = [
+    ErrorCorrectionLevel::L,
+    ErrorCorrectionLevel::M,
+    ErrorCorrectionLevel::Q,
+    ErrorCorrectionLevel::H,
+];
+
+// number of wrong codewords every block can correct, half of its error correction codewords without the ones
+// reserved for misdecode protection
+pub fn correctable_codewords(version: usize, error_correction: &ErrorCorrectionLevel) -> usize {
+    let meta = get_err_metadata(version, error_correction).unwrap();
+    let protection = MISDECODE_PROTECTION_CODEWORDS.iter()
+        .find(|option| option.0 == version && &option.1 == error_correction)
+        .map_or(0, |option| option.2);
+
+    (meta.words_per_block - protection) / 2
+}
+
+// number of codewords of every block with at least one data module in the area, found by following the placement
+// of the final message, a codeword counts as wrong no matter how many of its modules the logo covers
+pub fn touched_codewords(code: &QRCode, matrix: &QRMatrix, area: &LogoArea) -> Vec<usize> {
+    let meta = get_err_metadata(code.version(), code.error_correction()).unwrap();
+    let block_indices = interleaved_block_indices(&meta);
+
+    let mut touched = vec![false; block_indices.len()];
+    for (i, (x, y)) in matrix.data_module_positions().into_iter().enumerate() {
+        let in_area = (area.x..area.x + area.width).contains(&x) && (area.y..area.y + area.height).contains(&y);
+        // the remainder bits after the last codeword belong to no block
+        if in_area && i / 8 < touched.len() { touched[i / 8] = true; }
+    }
+
+    let mut counts = vec![0; meta.block_count()];
+    for (&block, _) in block_indices.iter().zip(&touched).filter(|(_, &touched)| touched) {
+        counts[block] += 1;
+    }
+
+    counts
+}
+
+#[derive(Debug, Clone)]
+pub struct LogoOptions {
+    // size of the logo as share of the symbol side, between 0.0 and 1.0
+    pub width: f64,
+    pub height: f64,
+    // the lowest level to try, higher levels are used if the logo does not fit the error correction budget
+    pub min_error_correction: ErrorCorrectionLevel,
+    // makes the logo smaller instead of failing if it does not even fit at level H
+    pub shrink: bool,
+}
+
+impl Default for LogoOptions {
+    fn default() -> Self {
+        Self {
+            width: 0.2,
+            height: 0.2,
+            min_error_correction: ErrorCorrectionLevel::M,
+            shrink: false,
+        }
+    }
+}
+
+// area of the logo in modules, (x, y) is its top left corner in the symbol without the quiet zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogoArea {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl LogoArea {
+    // area of the given size in the middle of the symbol
+    fn centered(symbol_size: usize, width: usize, height: usize) -> Self {
+        Self { x: (symbol_size - width) / 2, y: (symbol_size - height) / 2, width, height }
+    }
+}
+
+// side of the logo in modules, odd like the symbol size so that it sits exactly in the middle
+// None if the logo would be less than a module wide
+fn centered_length(share: f64, symbol_size: usize) -> Option<usize> {
+    let length = (share * symbol_size as f64).round() as usize;
+    let length = if length.is_multiple_of(2) { length.saturating_sub(1) } else { length.min(symbol_size) };
+    if length > 0 { Some(length) } else { None }
+}
+
+#[derive(Debug)]
+pub struct LogoQRCode {
+    code: QRCode,
+    area: LogoArea,
+}
+
+impl LogoQRCode {
+    // encodes the data at the lowest level from min_error_correction on at which the logo stays within the
+    // error correction budget, the logo is shrunk at level H if shrink is set and it is still too large
+    pub fn new(data: String, options: &LogoOptions) -> Result<Self, String> {
+        let valid_share = |share: f64| share > 0.0 && share <= 1.0;
+        if !valid_share(options.width) || !valid_share(options.height) {
+            return Err(String::from("Invalid logo size!"));
+        }
+
+        let first_level = LEVELS.iter().position(|level| level == &options.min_error_correction).unwrap();
+        let mut last_candidate = None;
+
+        for level in &LEVELS[first_level..] {
+            let code = QRCode::new(data.clone(), *level)?;
+            let matrix = code.gen_matrix().map_err(|err| format!("{:?}", err))?;
+            let size = matrix.size();
+            let (width, height) = match (centered_length(options.width, size), centered_length(options.height, size)) {
+                (Some(width), Some(height)) => (width, height),
+                _ => { return Err(String::from("Logo smaller than a module!")) },
+            };
+            let area = LogoArea::centered(size, width, height);
+
+            if fits_budget(&code, &matrix, &area) {
+                return Ok(Self { code, area });
+            }
+            last_candidate = Some((code, matrix, area));
+        }
+
+        let (code, matrix, requested) = last_candidate.unwrap();
+        if !options.shrink {
+            return Err(String::from("Logo too large for the error correction capacity!"));
+        }
+
+        // keeps the aspect ratio while taking two modules off the longer side at a time
+        let size = matrix.size();
+        let longer = requested.width.max(requested.height);
+        for length in (1..longer.saturating_sub(1)).rev().step_by(2) {
+            let scale = |side: usize| {
+                let scaled = (side * length + longer / 2) / longer;
+                if scaled.is_multiple_of(2) { scaled.saturating_sub(1) } else { scaled }
+            };
+            let (width, height) = (scale(requested.width), scale(requested.height));
+
+            // the shorter side only gets smaller from here on
+            if width == 0 || height == 0 { break; }
+
+            let area = LogoArea::centered(size, width, height);
+            if fits_budget(&code, &matrix, &area) {
+                return Ok(Self { code, area });
+            }
+        }
+
+        Err(String::from("Logo too large for the error correction capacity!"))
+    }
+
+    pub fn code(&self) -> &QRCode {
+        &self.code
+    }
+
+    pub fn area(&self) -> LogoArea {
+        self.area
+    }
+
+    // the symbol with every data module under the logo cleared to light
+    // finder, alignment and timing patterns and the format and version information under the logo stay in place
+    pub fn gen_matrix(&self) -> Result<QRMatrix, QRCodeError> {
+        let mut matrix = self.code.gen_matrix()?;
+        matrix.clear_data_modules(self.area.x, self.area.y, self.area.width, self.area.height);
+
+        Ok(matrix)
+    }
+}
+
+// function patterns are kept when clearing, so only codewords with data modules under the logo count against the
+// budget of their block
+fn fits_budget(code: &QRCode, matrix: &QRMatrix, area: &LogoArea) -> bool {
+    let budget = (correctable_codewords(code.version(), code.error_correction()) as f64 * LOGO_SAFETY_MARGIN) as usize;
+    touched_codewords(code, matrix, area).iter().all(|&count| count <= budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::{interleave_blocks, split_blocks};
+
+    #[test]
+    fn misdecode_protection_is_not_correctable() {
+        assert_eq!(correctable_codewords(1, &ErrorCorrectionLevel::L), 2);
+        assert_eq!(correctable_codewords(1, &ErrorCorrectionLevel::M), 4);
+        assert_eq!(correctable_codewords(1, &ErrorCorrectionLevel::Q), 6);
+        assert_eq!(correctable_codewords(2, &ErrorCorrectionLevel::L), 4);
+        assert_eq!(correctable_codewords(3, &ErrorCorrectionLevel::L), 7);
+        assert_eq!(correctable_codewords(4, &ErrorCorrectionLevel::L), 10);
+        assert_eq!(correctable_codewords(5, &ErrorCorrectionLevel::Q), 9);
+    }
+
+    // every codeword of a block is set to the number of the block, so the final message spells out the block order
+    #[test]
+    fn block_indices_follow_the_interleaving() {
+        for (version, level) in [(1, ErrorCorrectionLevel::H), (5, ErrorCorrectionLevel::Q), (13, ErrorCorrectionLevel::M), (40, ErrorCorrectionLevel::H)] {
+            let meta = get_err_metadata(version, &level).unwrap();
+            let blocks = split_blocks(&vec![0; meta.total_code_words()], &meta);
+            let data_blocks: Vec<Vec<u8>> = blocks.iter().enumerate().map(|(i, block)| vec![i as u8; block.len()]).collect();
+            let error_blocks: Vec<Vec<u8>> = (0..blocks.len()).map(|i| vec![i as u8; meta.words_per_block]).collect();
+
+            let message = interleave_blocks(&data_blocks, &error_blocks);
+            let expected: Vec<usize> = (0..message.len() / 8).map(|i| usize::from_str_radix(&message[i * 8..(i + 1) * 8], 2).unwrap()).collect();
+            assert_eq!(interleaved_block_indices(&meta), expected);
+        }
+    }
+
+    #[test]
+    fn touched_codewords_count_partly_covered_codewords() {
+        let code = QRCode::new(String::from("HELLO WORLD"), ErrorCorrectionLevel::Q).unwrap();
+        let matrix = code.gen_matrix().unwrap();
+
+        // the bottom right module is the first bit of the first codeword, the 2 x 4 modules above it hold the whole codeword
+        assert_eq!(touched_codewords(&code, &matrix, &LogoArea { x: 20, y: 20, width: 1, height: 1 }), [1]);
+        assert_eq!(touched_codewords(&code, &matrix, &LogoArea { x: 19, y: 17, width: 2, height: 4 }), [1]);
+        assert_eq!(touched_codewords(&code, &matrix, &LogoArea { x: 19, y: 16, width: 2, height: 5 }), [2]);
+        // finder patterns and format information hold no codewords
+        assert_eq!(touched_codewords(&code, &matrix, &LogoArea { x: 0, y: 0, width: 9, height: 9 }), [0]);
+    }
+
+    #[test]
+    fn logo_stays_within_the_budget_of_every_block() {
+        let options = LogoOptions { width: 0.3, height: 0.2, min_error_correction: ErrorCorrectionLevel::L, shrink: false };
+        let logo = LogoQRCode::new("x".repeat(300), &options).unwrap();
+        let code = logo.code();
+
+        let budget = (correctable_codewords(code.version(), code.error_correction()) as f64 * LOGO_SAFETY_MARGIN) as usize;
+        let touched = touched_codewords(code, &code.gen_matrix().unwrap(), &logo.area());
+        assert!(touched.len() > 1);
+        assert!(touched.iter().all(|&count| count <= budget));
+    }
+
+    #[test]
+    fn too_large_logo_is_refused_or_shrunk() {
+        let options = LogoOptions { width: 0.6, height: 0.6, ..Default::default() };
+        assert!(LogoQRCode::new(String::from("HELLO WORLD"), &options).is_err());
+
+        let logo = LogoQRCode::new(String::from("HELLO WORLD"), &LogoOptions { shrink: true, ..options }).unwrap();
+        assert_eq!(logo.code().error_correction(), &ErrorCorrectionLevel::H);
+        assert!(logo.area().width < 13);
+    }
+
+    #[test]
+    fn logo_sides_below_one_module_are_refused() {
+        assert_eq!(centered_length(0.01, 21), None);
+        assert_eq!(centered_length(0.05, 21), Some(1));
+        assert_eq!(centered_length(1.0, 21), Some(21));
+
+        let options = LogoOptions { width: 0.01, ..Default::default() };
+        assert_eq!(LogoQRCode::new(String::from("HELLO WORLD"), &options).unwrap_err(), "Logo smaller than a module!");
+
+        // the flat logo only fits once its height is scaled down to 0, which must not count as a fit
+        let options = LogoOptions { width: 1.0, height: 0.05, shrink: true, ..Default::default() };
+        assert_eq!(LogoQRCode::new(String::from("HELLO WORLD"), &options).unwrap_err(), "Logo too large for the error correction capacity!");
+    }
+}
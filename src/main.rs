@@ -1,12 +1,15 @@
-mod qrcode;
-mod solomon_reed;
-
-use qrcode::*;
-use solomon_reed::*;
+use qrcode::qrcode::*;
+use qrcode::render::RasterOptions;
+use qrcode::render::png::to_png;
+use qrcode::render::terminal::{to_terminal, TerminalOptions};
 
 fn main() {
     let qrcode_ = QRCode::new(String::from("HELLO WORLD"), ErrorCorrectionLevel::M).unwrap();
-    println!("{:?}", qrcode_.gen_error_codewords());
+    let matrix = qrcode_.gen_matrix().unwrap();
+    print!("{}", to_terminal(&matrix, &TerminalOptions::default()));
 
-    // pretty_print_polynomial(get_generator_polynomial( 20));
+    // writes the symbol as PNG if an output path is given
+    if let Some(path) = std::env::args().nth(1) {
+        std::fs::write(&path, to_png(&matrix, &RasterOptions::default())).expect("Could not write the PNG file!");
+    }
 }
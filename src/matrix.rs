@@ -0,0 +1,824 @@
+use crate::qrcode::{get_size_from_version, ErrorCorrectionLevel};
+use crate::micro::get_micro_size_from_version;
+use crate::rmqr::get_rmqr_dimensions;
+
+// source: https://www.thonky.com/qr-code-tutorial/module-placement-matrix
+
+// center coordinates of the alignment patterns, used for both rows and columns
+// source: https://www.thonky.com/qr-code-tutorial/alignment-pattern-locations
+const ALIGNMENT_PATTERN_POSITIONS: [&[usize]; 40] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+    &[6, 30, 54],
+    &[6, 32, 58],
+    &[6, 34, 62],
+    &[6, 26, 46, 66],
+    &[6, 26, 48, 70],
+    &[6, 26, 50, 74],
+    &[6, 30, 54, 78],
+    &[6, 30, 56, 82],
+    &[6, 30, 58, 86],
+    &[6, 34, 62, 90],
+    &[6, 28, 50, 72, 94],
+    &[6, 26, 50, 74, 98],
+    &[6, 30, 54, 78, 102],
+    &[6, 28, 54, 80, 106],
+    &[6, 32, 58, 84, 110],
+    &[6, 30, 58, 86, 114],
+    &[6, 34, 62, 90, 118],
+    &[6, 26, 50, 74, 98, 122],
+    &[6, 30, 54, 78, 102, 126],
+    &[6, 26, 52, 78, 104, 130],
+    &[6, 30, 56, 82, 108, 134],
+    &[6, 34, 60, 86, 112, 138],
+    &[6, 30, 58, 86, 114, 142],
+    &[6, 34, 62, 90, 118, 146],
+    &[6, 30, 54, 78, 102, 126, 150],
+    &[6, 24, 50, 76, 102, 128, 154],
+    &[6, 28, 54, 80, 106, 132, 158],
+    &[6, 32, 58, 84, 110, 136, 162],
+    &[6, 26, 54, 82, 110, 138, 166],
+    &[6, 30, 58, 86, 114, 142, 170],
+];
+
+pub fn get_alignment_pattern_positions(version: usize) -> &'static [usize] {
+    ALIGNMENT_PATTERN_POSITIONS[version - 1]
+}
+
+// center columns of the rMQR alignment patterns on the top and bottom edge, depending on the symbol width
+const RMQR_ALIGNMENT_PATTERN_COLUMNS: [(usize, &[usize]); 6] = [
+    (27,  &[]),
+    (43,  &[21]),
+    (59,  &[19, 39]),
+    (77,  &[25, 51]),
+    (99,  &[23, 49, 75]),
+    (139, &[27, 55, 83, 111]),
+];
+
+fn get_rmqr_alignment_pattern_columns(width: usize) -> &'static [usize] {
+    RMQR_ALIGNMENT_PATTERN_COLUMNS.iter()
+        .find(|option| option.0 == width)
+        .map_or(&[], |option| option.1)
+}
+
+pub const NUM_MASKS: usize = 8;
+pub const NUM_MICRO_MASKS: usize = 4;
+
+// the four micro masks reuse the conditions of the regular mask patterns 1, 4, 6 and 7
+const MICRO_MASK_PATTERNS: [usize; NUM_MICRO_MASKS] = [1, 4, 6, 7];
+
+// rMQR symbols always use the condition of mask pattern 4
+const RMQR_MASK_PATTERNS: [usize; 1] = [4];
+
+// generator polynomials of the BCH codes protecting the format and version information
+const FORMAT_GENERATOR: u32 = 0b101_0011_0111;
+const VERSION_GENERATOR: u32 = 0b1_1111_0010_0101;
+const FORMAT_XOR_MASK: u32 = 0b101_0100_0001_0010;
+const MICRO_FORMAT_XOR_MASK: u32 = 0b100_0100_0100_0101;
+const RMQR_FINDER_FORMAT_XOR_MASK: u32 = 0b01_1111_1010_1011_0010;
+const RMQR_SUB_FINDER_FORMAT_XOR_MASK: u32 = 0b10_0000_1010_0111_1011;
+
+// data followed by the remainder of its division by generator, both read as polynomials over GF(2)
+fn bch_encode(data: u32, generator: u32) -> u32 {
+    let generator_degree = 31 - generator.leading_zeros();
+    let mut remainder = data << generator_degree;
+
+    while remainder != 0 && 31 - remainder.leading_zeros() >= generator_degree {
+        let shift = (31 - remainder.leading_zeros()) - generator_degree;
+        remainder ^= generator << shift;
+    }
+
+    (data << generator_degree) | remainder
+}
+
+// 15 bit format string: 2 bits error correction level and 3 bits mask, 10 bits BCH(15,5) and the fixed xor mask
+// source: https://www.thonky.com/qr-code-tutorial/format-version-information
+pub fn gen_format_bits(error_correction: &ErrorCorrectionLevel, mask: usize) -> u32 {
+    let data = (error_correction.format_bits() << 3) | mask as u32;
+    bch_encode(data, FORMAT_GENERATOR) ^ FORMAT_XOR_MASK
+}
+
+// 15 bit micro format string: 3 bits symbol number and 2 bits mask, protected by the same BCH code as the regular one
+pub fn gen_micro_format_bits(symbol_number: usize, mask: usize) -> u32 {
+    let data = ((symbol_number as u32) << 2) | mask as u32;
+    bch_encode(data, FORMAT_GENERATOR) ^ MICRO_FORMAT_XOR_MASK
+}
+
+// 18 bit rMQR format string: 1 bit error correction level and 5 bits version indicator, protected by the BCH(18,6) code
+// of the version information, the copies beside the finder and the sub finder pattern use different xor masks
+pub fn gen_rmqr_format_bits(error_correction: &ErrorCorrectionLevel, version_indicator: usize) -> [u32; 2] {
+    let level = match error_correction {
+        ErrorCorrectionLevel::H => 1,
+        _                       => 0,
+    };
+    let bits = bch_encode((level << 5) | version_indicator as u32, VERSION_GENERATOR);
+
+    [bits ^ RMQR_FINDER_FORMAT_XOR_MASK, bits ^ RMQR_SUB_FINDER_FORMAT_XOR_MASK]
+}
+
+// 18 bit version string: 6 bits version and 12 bits BCH(18,6), only used from version 7 on
+pub fn gen_version_bits(version: usize) -> u32 {
+    bch_encode(version as u32, VERSION_GENERATOR)
+}
+
+// finder like sequence of rule 3, searched for in both directions
+const FINDER_LIKE_PATTERN: [bool; 11] = [true, false, true, true, true, false, true, false, false, false, false];
+
+// mask condition of the given pattern for the module in row y and column x, true means the module is flipped
+// source: https://www.thonky.com/qr-code-tutorial/mask-patterns
+fn mask_condition(mask: usize, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (y + x).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (y + x).is_multiple_of(3),
+        4 => (y / 2 + x / 3).is_multiple_of(2),
+        5 => (y * x) % 2 + (y * x) % 3 == 0,
+        6 => ((y * x) % 2 + (y * x) % 3).is_multiple_of(2),
+        7 => ((y + x) % 2 + (y * x) % 3).is_multiple_of(2),
+        _ => panic!("Invalid mask pattern!"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolType {
+    QR,
+    Micro,
+    RMQR,
+}
+
+// the module grid of a symbol, x is the column and y the row, (0, 0) is the top left corner
+// dark modules are stored as true, reserved marks function modules that data and masks must skip
+#[derive(Debug, Clone)]
+pub struct QRMatrix {
+    symbol_type: SymbolType,
+    version: usize,
+    width: usize,
+    height: usize,
+    modules: Vec<bool>,
+    reserved: Vec<bool>,
+}
+
+impl QRMatrix {
+    pub fn new(version: usize) -> Self {
+        assert!((1..=40).contains(&version), "Invalid version for QRMatrix!");
+
+        let size = get_size_from_version(version);
+        let mut matrix = Self {
+            symbol_type: SymbolType::QR,
+            version,
+            width: size,
+            height: size,
+            modules: vec![false; size * size],
+            reserved: vec![false; size * size],
+        };
+
+        matrix.place_finder_patterns();
+        matrix.place_alignment_patterns();
+        matrix.place_timing_patterns();
+        matrix.place_dark_module();
+        matrix.reserve_format_areas();
+        matrix.place_version_info();
+
+        matrix
+    }
+
+    // micro symbol with a single finder pattern and the timing patterns along the top row and the left column
+    // source: https://www.qrcode.com/en/codes/microqr.html
+    pub fn new_micro(version: usize) -> Self {
+        assert!((1..=4).contains(&version), "Invalid version for micro QRMatrix!");
+
+        let size = get_micro_size_from_version(version);
+        let mut matrix = Self {
+            symbol_type: SymbolType::Micro,
+            version,
+            width: size,
+            height: size,
+            modules: vec![false; size * size],
+            reserved: vec![false; size * size],
+        };
+
+        matrix.place_finder_pattern(0, 0);
+        matrix.place_timing_patterns();
+        matrix.reserve_format_areas();
+
+        matrix
+    }
+
+    // rectangular symbol with a finder pattern on the left, a sub finder pattern in the bottom right corner,
+    // corner patterns in the two other corners and alignment patterns on the top and bottom edge
+    // source: ISO/IEC 23941
+    pub fn new_rmqr(version: usize) -> Self {
+        assert!((1..=32).contains(&version), "Invalid version for rMQR QRMatrix!");
+
+        let (width, height) = get_rmqr_dimensions(version);
+        let mut matrix = Self {
+            symbol_type: SymbolType::RMQR,
+            version,
+            width,
+            height,
+            modules: vec![false; width * height],
+            reserved: vec![false; width * height],
+        };
+
+        matrix.place_finder_pattern(0, 0);
+        matrix.place_rmqr_corner_patterns();
+        matrix.place_rmqr_sub_finder_pattern();
+        matrix.place_rmqr_alignment_patterns();
+        matrix.place_timing_patterns();
+        matrix.reserve_rmqr_format_areas();
+
+        matrix
+    }
+
+    pub fn symbol_type(&self) -> SymbolType {
+        self.symbol_type
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    // side length of the square regular and micro symbols
+    pub fn size(&self) -> usize {
+        self.width
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.width + x]
+    }
+
+    pub fn is_reserved(&self, x: usize, y: usize) -> bool {
+        self.reserved[y * self.width + x]
+    }
+
+    fn set_function_module(&mut self, x: usize, y: usize, dark: bool) {
+        let idx = y * self.width + x;
+        self.modules[idx] = dark;
+        self.reserved[idx] = true;
+    }
+
+    // places a 7x7 finder pattern with its top left corner at (x, y) and the light separator around it
+    fn place_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -1..=7i32 {
+            for dx in -1..=7i32 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if xx < 0 || yy < 0 || xx >= self.width as i32 || yy >= self.height as i32 { continue; }
+
+                // outer ring and inner 3x3 square are dark, the separator is outside of 0..=6
+                let in_pattern = (0..=6).contains(&dx) && (0..=6).contains(&dy);
+                let on_ring = dx == 0 || dx == 6 || dy == 0 || dy == 6;
+                let in_center = (2..=4).contains(&dx) && (2..=4).contains(&dy);
+
+                self.set_function_module(xx as usize, yy as usize, in_pattern && (on_ring || in_center));
+            }
+        }
+    }
+
+    fn place_finder_patterns(&mut self) {
+        let far = self.size() - 7;
+        self.place_finder_pattern(0, 0);
+        self.place_finder_pattern(far, 0);
+        self.place_finder_pattern(0, far);
+    }
+
+    fn place_alignment_patterns(&mut self) {
+        let positions = get_alignment_pattern_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+
+        for (i, &cy) in positions.iter().enumerate() {
+            for (j, &cx) in positions.iter().enumerate() {
+                // skip the three positions that would overlap the finder patterns
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) { continue; }
+
+                for dy in -2..=2i32 {
+                    for dx in -2..=2i32 {
+                        let dark = dx.abs().max(dy.abs()) != 1;
+                        self.set_function_module((cx as i32 + dx) as usize, (cy as i32 + dy) as usize, dark);
+                    }
+                }
+            }
+        }
+    }
+
+    // top left corner and side length of the finder patterns, including the 5x5 sub finder pattern of rMQR symbols
+    // each one is a dark ring around a light ring around a dark square
+    pub fn finder_patterns(&self) -> Vec<(usize, usize, usize)> {
+        match self.symbol_type {
+            SymbolType::QR    => {
+                let far = self.size() - 7;
+                vec![(0, 0, 7), (far, 0, 7), (0, far, 7)]
+            },
+            SymbolType::Micro => vec![(0, 0, 7)],
+            SymbolType::RMQR  => vec![(0, 0, 7), (self.width - 5, self.height - 5, 5)],
+        }
+    }
+
+    // width of the light border readers need around the symbol, in modules
+    pub fn quiet_zone(&self) -> usize {
+        match self.symbol_type {
+            SymbolType::QR    => 4,
+            SymbolType::Micro => 2,
+            SymbolType::RMQR  => 2,
+        }
+    }
+
+    // regular symbols run the timing patterns between the finder patterns in row and column 6,
+    // micro symbols along the outer edge up to the end of the symbol, rMQR symbols fill the gaps between their patterns
+    fn place_timing_patterns(&mut self) {
+        let (line, end) = match self.symbol_type {
+            SymbolType::QR    => (6, self.size() - 8),
+            SymbolType::Micro => (0, self.size()),
+            SymbolType::RMQR  => {
+                self.place_rmqr_timing_patterns();
+                return;
+            },
+        };
+
+        for i in 8..end {
+            let dark = i % 2 == 0;
+            self.set_function_module(i, line, dark);
+            self.set_function_module(line, i, dark);
+        }
+    }
+
+    // L shaped patterns in the top right and bottom left corner, the bottom left one only has its vertical part
+    // from height 11 on as the separator of the finder pattern takes its place before
+    fn place_rmqr_corner_patterns(&mut self) {
+        let (right, bottom) = (self.width - 1, self.height - 1);
+
+        self.set_function_module(right, 0, true);
+        self.set_function_module(right, 1, true);
+        self.set_function_module(right - 1, 0, true);
+        self.set_function_module(right - 1, 1, false);
+
+        if self.height < 9 { return; }
+
+        for x in 0..3 {
+            self.set_function_module(x, bottom, true);
+        }
+        if self.height >= 11 {
+            self.set_function_module(0, bottom - 1, true);
+            self.set_function_module(1, bottom - 1, false);
+        }
+    }
+
+    // 5x5 pattern in the bottom right corner, a dark ring around a light ring and a dark center
+    fn place_rmqr_sub_finder_pattern(&mut self) {
+        for dy in 0..5 {
+            for dx in 0..5 {
+                let ring = dx.max(dy).max(4 - dx).max(4 - dy);
+                self.set_function_module(self.width - 5 + dx, self.height - 5 + dy, ring != 3);
+            }
+        }
+    }
+
+    // 3x3 patterns with a light center, touching the top and the bottom edge
+    fn place_rmqr_alignment_patterns(&mut self) {
+        for &cx in get_rmqr_alignment_pattern_columns(self.width) {
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    let dark = dx != 1 || dy != 1;
+                    self.set_function_module(cx + dx - 1, dy, dark);
+                    self.set_function_module(cx + dx - 1, self.height - 1 - dy, dark);
+                }
+            }
+        }
+    }
+
+    // the top and bottom edge and the columns of the left edge, the alignment patterns and the right edge
+    // alternate between dark and light wherever no other pattern has been placed
+    fn place_rmqr_timing_patterns(&mut self) {
+        for x in 0..self.width {
+            for y in [0, self.height - 1] {
+                if !self.is_reserved(x, y) { self.set_function_module(x, y, x % 2 == 0); }
+            }
+        }
+
+        let columns = get_rmqr_alignment_pattern_columns(self.width);
+        for &x in [0].iter().chain(columns).chain([self.width - 1].iter()) {
+            for y in 0..self.height {
+                if !self.is_reserved(x, y) { self.set_function_module(x, y, y % 2 == 0); }
+            }
+        }
+    }
+
+    // the single dark module beside the bottom left finder pattern
+    fn place_dark_module(&mut self) {
+        self.set_function_module(8, 4 * self.version + 9, true);
+    }
+
+    // reserves the two copies of the 15 bit format information, filled in after masking
+    // micro symbols only have the copy around their finder pattern
+    fn reserve_format_areas(&mut self) {
+        if self.symbol_type == SymbolType::Micro {
+            for i in 1..9 {
+                self.set_function_module(i, 8, false);
+                self.set_function_module(8, i, false);
+            }
+            return;
+        }
+
+        for i in 0..9 {
+            if i != 6 {
+                self.set_function_module(i, 8, false);
+                self.set_function_module(8, i, false);
+            }
+        }
+
+        for i in 0..8 {
+            self.set_function_module(self.size() - 1 - i, 8, false);
+        }
+        for i in 0..7 {
+            self.set_function_module(8, self.size() - 1 - i, false);
+        }
+    }
+
+    // writes both copies of the format information, bit 14 is the most significant bit
+    pub fn place_format_info(&mut self, error_correction: &ErrorCorrectionLevel, mask: usize) {
+        let bits = gen_format_bits(error_correction, mask);
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        // first copy around the top left finder pattern, skipping the timing patterns
+        for i in 0..6 {
+            self.set_function_module(8, i, bit(i));
+        }
+        self.set_function_module(8, 7, bit(6));
+        self.set_function_module(8, 8, bit(7));
+        self.set_function_module(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function_module(14 - i, 8, bit(i));
+        }
+
+        // second copy split between the top right and bottom left finder patterns
+        for i in 0..8 {
+            self.set_function_module(self.size() - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function_module(8, self.size() - 15 + i, bit(i));
+        }
+    }
+
+    // positions of format bit i beside the finder pattern and beside the sub finder pattern,
+    // each copy fills a 3x5 block column by column and continues with 3 modules on its side
+    fn rmqr_format_positions(&self, i: usize) -> [(usize, usize); 2] {
+        let finder = (8 + i / 5, 1 + i % 5);
+        let sub_finder = if i < 15 {
+            (self.width - 8 + i / 5, self.height - 6 + i % 5)
+        } else {
+            (self.width - 20 + i, self.height - 6)
+        };
+
+        [finder, sub_finder]
+    }
+
+    fn reserve_rmqr_format_areas(&mut self) {
+        for i in 0..18 {
+            for (x, y) in self.rmqr_format_positions(i) {
+                self.set_function_module(x, y, false);
+            }
+        }
+    }
+
+    // writes both copies of the rMQR format information, bit 0 is the least significant bit
+    pub fn place_rmqr_format_info(&mut self, error_correction: &ErrorCorrectionLevel, version_indicator: usize) {
+        let copies = gen_rmqr_format_bits(error_correction, version_indicator);
+
+        for i in 0..18 {
+            for (bits, (x, y)) in copies.iter().zip(self.rmqr_format_positions(i)) {
+                self.set_function_module(x, y, (bits >> i) & 1 == 1);
+            }
+        }
+    }
+
+    // writes the single copy of the micro format information, bit 14 is next to the left edge
+    pub fn place_micro_format_info(&mut self, symbol_number: usize, mask: usize) {
+        let bits = gen_micro_format_bits(symbol_number, mask);
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        for i in 0..7 {
+            self.set_function_module(8, i + 1, bit(i));
+        }
+        for i in 7..15 {
+            self.set_function_module(15 - i, 8, bit(i));
+        }
+    }
+
+    // writes the two 6x3 version information blocks, only present from version 7 on
+    fn place_version_info(&mut self) {
+        if self.version < 7 { return; }
+
+        let bits = gen_version_bits(self.version);
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let (a, b) = (self.size() - 11 + i % 3, i / 3);
+
+            self.set_function_module(a, b, dark);
+            self.set_function_module(b, a, dark);
+        }
+    }
+
+    // the non reserved modules in the order the final message is placed, starting at the bottom right corner
+    // and moving in two module wide columns alternating upwards and downwards
+    pub fn data_module_positions(&self) -> Vec<(usize, usize)> {
+        let mut positions = Vec::new();
+        let mut upwards = true;
+        // the right edge of rMQR symbols only holds function patterns
+        let mut right = match self.symbol_type {
+            SymbolType::RMQR => self.width - 2,
+            _                => self.width - 1,
+        };
+
+        while right > 0 {
+            // the vertical timing pattern of regular symbols shifts all columns on its left by one
+            if self.symbol_type == SymbolType::QR && right == 6 { right = 5; }
+
+            for step in 0..self.height {
+                let y = if upwards { self.height - 1 - step } else { step };
+
+                for x in [right, right - 1] {
+                    if !self.is_reserved(x, y) { positions.push((x, y)); }
+                }
+            }
+
+            upwards = !upwards;
+            right = right.saturating_sub(2);
+        }
+
+        positions
+    }
+
+    // writes the final message bits into the data modules, the modules after the message are light
+    pub fn place_data(&mut self, bits: &str) {
+        let positions = self.data_module_positions();
+        assert!(positions.len() >= bits.len(), "Message does not fit into the data modules of the matrix!");
+
+        let bits = bits.as_bytes();
+        for (i, (x, y)) in positions.into_iter().enumerate() {
+            self.modules[y * self.width + x] = i < bits.len() && bits[i] == b'1';
+        }
+    }
+
+    // sets the data modules in the width x height area with its top left corner at (x, y) to light
+    // function patterns in the area are kept, returns the number of cleared modules
+    pub fn clear_data_modules(&mut self, x: usize, y: usize, width: usize, height: usize) -> usize {
+        let mut cleared = 0;
+        for yy in y..(y + height).min(self.height) {
+            for xx in x..(x + width).min(self.width) {
+                if self.is_reserved(xx, yy) { continue; }
+
+                self.modules[yy * self.width + xx] = false;
+                cleared += 1;
+            }
+        }
+
+        cleared
+    }
+
+    // flips every data module for which the condition of the mask pattern holds, function modules are untouched
+    // micro symbols number their four masks separately, rMQR symbols only have mask 0
+    pub fn apply_mask(&mut self, mask: usize) {
+        let pattern = match self.symbol_type {
+            SymbolType::QR    => mask,
+            SymbolType::Micro => MICRO_MASK_PATTERNS[mask],
+            SymbolType::RMQR  => RMQR_MASK_PATTERNS[mask],
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.is_reserved(x, y) && mask_condition(pattern, x, y) {
+                    let idx = y * self.width + x;
+                    self.modules[idx] = !self.modules[idx];
+                }
+            }
+        }
+    }
+
+    // sum of the four penalty rules, the mask with the lowest penalty is used for the symbol
+    // source: https://www.thonky.com/qr-code-tutorial/data-masking
+    pub fn penalty(&self) -> usize {
+        self.penalty_runs() + self.penalty_blocks() + self.penalty_finder_like() + self.penalty_balance()
+    }
+
+    // micro symbols prefer masks that leave many dark modules on the right and bottom edge, the highest score wins
+    // the timing patterns in the first row and column are not counted
+    pub fn micro_score(&self) -> usize {
+        let last = self.size() - 1;
+        let right_edge = (1..self.size()).filter(|&y| self.get(last, y)).count();
+        let bottom_edge = (1..self.size()).filter(|&x| self.get(x, last)).count();
+
+        right_edge.min(bottom_edge) * 16 + right_edge.max(bottom_edge)
+    }
+
+    // the module at position i of row/column line, read horizontally or vertically
+    fn get_in_line(&self, horizontal: bool, line: usize, i: usize) -> bool {
+        if horizontal { self.get(i, line) } else { self.get(line, i) }
+    }
+
+    // rule 1: 3 points for every run of five modules of the same color, plus 1 for each further module
+    fn penalty_runs(&self) -> usize {
+        let mut penalty = 0;
+
+        for horizontal in [true, false] {
+            for line in 0..self.size() {
+                let mut run_color = self.get_in_line(horizontal, line, 0);
+                let mut run_len = 1;
+
+                for i in 1..self.size() {
+                    let color = self.get_in_line(horizontal, line, i);
+                    if color == run_color {
+                        run_len += 1;
+                        if run_len == 5 { penalty += 3; }
+                        else if run_len > 5 { penalty += 1; }
+                    } else {
+                        run_color = color;
+                        run_len = 1;
+                    }
+                }
+            }
+        }
+
+        penalty
+    }
+
+    // rule 2: 3 points for every 2x2 block of the same color, overlapping blocks count separately
+    fn penalty_blocks(&self) -> usize {
+        let mut penalty = 0;
+
+        for y in 0..self.size() - 1 {
+            for x in 0..self.size() - 1 {
+                let color = self.get(x, y);
+                if color == self.get(x + 1, y) && color == self.get(x, y + 1) && color == self.get(x + 1, y + 1) {
+                    penalty += 3;
+                }
+            }
+        }
+
+        penalty
+    }
+
+    // rule 3: 40 points for every occurrence of 10111010000 or 00001011101 in a row or column
+    fn penalty_finder_like(&self) -> usize {
+        let mut penalty = 0;
+        let len = FINDER_LIKE_PATTERN.len();
+
+        for horizontal in [true, false] {
+            for line in 0..self.size() {
+                for start in 0..=self.size() - len {
+                    let forwards = (0..len).all(|i| self.get_in_line(horizontal, line, start + i) == FINDER_LIKE_PATTERN[i]);
+                    let backwards = (0..len).all(|i| self.get_in_line(horizontal, line, start + i) == FINDER_LIKE_PATTERN[len - 1 - i]);
+
+                    if forwards { penalty += 40; }
+                    if backwards { penalty += 40; }
+                }
+            }
+        }
+
+        penalty
+    }
+
+    // rule 4: 10 points for every 5% the ratio of dark modules deviates from 50%
+    fn penalty_balance(&self) -> usize {
+        let total = self.modules.len();
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+
+        let percent = dark * 100 / total;
+        let prev_multiple = percent - percent % 5;
+        let next_multiple = prev_multiple + 5;
+
+        let deviation = (prev_multiple as i32 - 50).abs().min((next_multiple as i32 - 50).abs()) as usize;
+        deviation / 5 * 10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::{get_err_metadata, REMAINDER_BITS};
+
+    // centres of all alignment patterns, leaving out the three corners taken by the finder patterns
+    fn alignment_centres(version: usize) -> Vec<(usize, usize)> {
+        let positions = get_alignment_pattern_positions(version);
+        let last = positions.len() - 1;
+        let mut centres = Vec::new();
+        for (i, &y) in positions.iter().enumerate() {
+            for (j, &x) in positions.iter().enumerate() {
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) { continue; }
+                centres.push((x, y));
+            }
+        }
+        centres
+    }
+
+    // source: ISO/IEC 18004:2015 annex E, table E.1
+    #[test]
+    fn alignment_pattern_positions_follow_the_spec() {
+        assert_eq!(get_alignment_pattern_positions(1), &[] as &[usize]);
+        assert_eq!(get_alignment_pattern_positions(2), &[6, 18]);
+        assert_eq!(get_alignment_pattern_positions(7), &[6, 22, 38]);
+        assert_eq!(get_alignment_pattern_positions(40), &[6, 30, 58, 86, 114, 142, 170]);
+
+        assert_eq!(alignment_centres(2), vec![(18, 18)]);
+        assert_eq!(alignment_centres(7), vec![(22, 6), (6, 22), (22, 22), (38, 22), (22, 38), (38, 38)]);
+        assert_eq!(alignment_centres(40).len(), 46);
+    }
+
+    #[test]
+    fn alignment_patterns_are_placed_around_their_centres() {
+        for &version in &[2, 7, 40] {
+            let matrix = QRMatrix::new(version);
+            for (cx, cy) in alignment_centres(version) {
+                for y in cy - 2..=cy + 2 {
+                    for x in cx - 2..=cx + 2 {
+                        let ring = (x as i32 - cx as i32).abs().max((y as i32 - cy as i32).abs());
+                        assert_eq!(matrix.get(x, y), ring != 1, "version {} module ({}, {})", version, x, y);
+                        assert!(matrix.is_reserved(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    // source: ISO/IEC 18004:2015 table 1
+    const TOTAL_CODEWORDS: [usize; 40] = [
+          26,   44,   70,  100,  134,  172,  196,  242,  292,  346,  404,  466,  532,  581,  655,  733,  815,  901,  991, 1085,
+        1156, 1258, 1364, 1474, 1588, 1706, 1828, 1921, 2051, 2185, 2323, 2465, 2611, 2761, 2876, 3034, 3196, 3362, 3532, 3706,
+    ];
+
+    #[test]
+    fn data_modules_hold_all_codewords_and_the_remainder_bits() {
+        let levels = [ErrorCorrectionLevel::L, ErrorCorrectionLevel::M, ErrorCorrectionLevel::Q, ErrorCorrectionLevel::H];
+        for version in 1..=40 {
+            let modules = QRMatrix::new(version).data_module_positions().len();
+            assert_eq!(modules, TOTAL_CODEWORDS[version - 1] * 8 + REMAINDER_BITS[version - 1], "version {}", version);
+
+            for level in levels.iter() {
+                let meta = get_err_metadata(version, level).unwrap();
+                let codewords = meta.total_code_words() + meta.block_count() * meta.words_per_block;
+                assert_eq!(codewords, TOTAL_CODEWORDS[version - 1], "version {} level {:?}", version, level);
+            }
+        }
+    }
+
+    // source: ISO/IEC 18004:2015 annex C, table C.1
+    #[test]
+    fn format_bits_follow_the_spec() {
+        let table = [
+            (ErrorCorrectionLevel::L, [0x77C4, 0x72F3, 0x7DAA, 0x789D, 0x662F, 0x6318, 0x6C41, 0x6976]),
+            (ErrorCorrectionLevel::M, [0x5412, 0x5125, 0x5E7C, 0x5B4B, 0x45F9, 0x40CE, 0x4F97, 0x4AA0]),
+            (ErrorCorrectionLevel::Q, [0x355F, 0x3068, 0x3F31, 0x3A06, 0x24B4, 0x2183, 0x2EDA, 0x2BED]),
+            (ErrorCorrectionLevel::H, [0x1689, 0x13BE, 0x1CE7, 0x19D0, 0x0762, 0x0255, 0x0D0C, 0x083B]),
+        ];
+        for (level, bits) in table.iter() {
+            for (mask, &expected) in bits.iter().enumerate() {
+                assert_eq!(gen_format_bits(level, mask), expected, "level {:?} mask {}", level, mask);
+            }
+        }
+    }
+
+    // source: ISO/IEC 18004:2015 annex D, table D.1
+    #[test]
+    fn version_bits_follow_the_spec() {
+        assert_eq!(gen_version_bits(7), 0x07C94);
+        assert_eq!(gen_version_bits(8), 0x085BC);
+        assert_eq!(gen_version_bits(21), 0x15683);
+        assert_eq!(gen_version_bits(40), 0x28C69);
+    }
+
+    #[test]
+    fn both_version_blocks_are_placed() {
+        let matrix = QRMatrix::new(7);
+        let far = matrix.size() - 11;
+
+        // the top right block is 3 modules wide and the bottom left one 3 modules high, both start with the lowest bit
+        let mut top_right = 0;
+        let mut bottom_left = 0;
+        for i in (0..18).rev() {
+            top_right = (top_right << 1) | matrix.get(far + i % 3, i / 3) as u32;
+            bottom_left = (bottom_left << 1) | matrix.get(i / 3, far + i % 3) as u32;
+            assert!(matrix.is_reserved(far + i % 3, i / 3) && matrix.is_reserved(i / 3, far + i % 3));
+        }
+        assert_eq!(top_right, 0x07C94);
+        assert_eq!(bottom_left, 0x07C94);
+
+        let matrix = QRMatrix::new(6);
+        let far = matrix.size() - 11;
+        assert!((0..18).all(|i| !matrix.is_reserved(far + i % 3, i / 3) && !matrix.is_reserved(i / 3, far + i % 3)));
+    }
+}
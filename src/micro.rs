@@ -0,0 +1,418 @@
+use std::cmp::Reverse;
+
+use crate::matrix::{QRMatrix, NUM_MICRO_MASKS};
+use crate::qrcode::{QRCodeError, QREncoding, ErrorCorrectionLevel, encode_data, find_encoding};
+use crate::segment::Segment;
+
+use crate::solomon_reed::ReedSolomon;
+
+// source: https://www.qrcode.com/en/codes/microqr.html
+
+// capacities of the micro versions M1 to M4, 0 if the version does not support the encoding or level
+// M1 only detects errors and is listed under level L
+const MICRO_CHAR_CAPACITY: [(QREncoding, ErrorCorrectionLevel, [usize; 4]); 12]
+    = [
+        (QREncoding::Numeric,       ErrorCorrectionLevel::L, [5,10,23,35]),
+        (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::L, [0,6,14,21]),
+        (QREncoding::Byte,          ErrorCorrectionLevel::L, [0,0,9,15]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::L, [0,0,6,9]),
+        (QREncoding::Numeric,       ErrorCorrectionLevel::M, [0,8,18,30]),
+        (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::M, [0,5,11,18]),
+        (QREncoding::Byte,          ErrorCorrectionLevel::M, [0,0,7,13]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::M, [0,0,4,8]),
+        (QREncoding::Numeric,       ErrorCorrectionLevel::Q, [0,0,0,21]),
+        (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::Q, [0,0,0,13]),
+        (QREncoding::Byte,          ErrorCorrectionLevel::Q, [0,0,0,9]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::Q, [0,0,0,5]),
+    ];
+
+// looks up the smallest micro version that holds the data, 0 if none does
+fn get_micro_size(data_size: usize, encoding: &QREncoding, error_correction: &ErrorCorrectionLevel) -> usize {
+    let current_option = (encoding, error_correction);
+
+    for option in MICRO_CHAR_CAPACITY {
+        if &option.0 == current_option.0 && &option.1 == current_option.1 {
+            for i in 0..4 {
+                let size = option.2[i];
+                if size > 0 && data_size <= size { return i + 1 }
+            }
+        }
+    }
+
+    0
+}
+
+pub fn get_micro_size_from_version(version: usize) -> usize {
+    version * 2 + 9
+}
+
+// every micro symbol has a single block, so the table only holds the number of data bits and error correction codewords
+// the position in the table is the symbol number stored in the format information
+// 0: data bits
+// 1: error correction codewords
+const MICRO_ERROR_CORRECTION_DATA: [(usize, ErrorCorrectionLevel, [usize; 2]); 8] = [
+    (1, ErrorCorrectionLevel::L, [20,2]),
+    (2, ErrorCorrectionLevel::L, [40,5]),
+    (2, ErrorCorrectionLevel::M, [32,6]),
+    (3, ErrorCorrectionLevel::L, [84,6]),
+    (3, ErrorCorrectionLevel::M, [68,8]),
+    (4, ErrorCorrectionLevel::L, [128,8]),
+    (4, ErrorCorrectionLevel::M, [112,10]),
+    (4, ErrorCorrectionLevel::Q, [80,14]),
+];
+
+#[derive(Debug)]
+struct MicroErrorCorrectionMetaData {
+    symbol_number: usize,
+    data_bits: usize,
+    words_per_block: usize,
+}
+
+fn get_micro_err_metadata(version: usize, error_correction: &ErrorCorrectionLevel) -> Option<MicroErrorCorrectionMetaData> {
+    MICRO_ERROR_CORRECTION_DATA.iter()
+        .position(|option| option.0 == version && &option.1 == error_correction)
+        .map(|symbol_number| {
+            let [data_bits, words_per_block] = MICRO_ERROR_CORRECTION_DATA[symbol_number].2;
+            MicroErrorCorrectionMetaData { symbol_number, data_bits, words_per_block }
+        })
+}
+
+// the mode indicator is version - 1 bits long, M1 only supports numeric data and has none
+fn get_micro_encoding(encoding: &QREncoding, version: usize) -> Result<String, QRCodeError> {
+    let mode = match encoding {
+        QREncoding::Numeric      => 0,
+        QREncoding::AlphaNumeric => 1,
+        QREncoding::Byte         => 2,
+        QREncoding::Kanji        => 3,
+        _                        => { return Err(QRCodeError::UnsupportedCharacter); }
+    };
+
+    if version == 1 { return Ok(String::new()); }
+    Ok(format!("{:0width$b}", mode, width = version - 1))
+}
+
+// length of the character count indicator per micro version
+fn get_micro_count_bits(encoding: &QREncoding, version: usize) -> Result<usize, QRCodeError> {
+    let widths = match encoding {
+        QREncoding::Numeric      => [3, 4, 5, 6],
+        QREncoding::AlphaNumeric => [0, 3, 4, 5],
+        QREncoding::Byte         => [0, 0, 4, 5],
+        QREncoding::Kanji        => [0, 0, 3, 4],
+        _                        => { return Err(QRCodeError::UnsupportedCharacter); }
+    };
+
+    match widths.get(version.wrapping_sub(1)) {
+        Some(&width) if width > 0 => Ok(width),
+        _                         => Err(QRCodeError::UnsupportedVersion),
+    }
+}
+
+#[derive(Debug)]
+pub struct MicroQRCode {
+    raw_data: String,
+    segment: Segment,
+    error_correction: ErrorCorrectionLevel,
+    version: usize,
+    err_metadata: MicroErrorCorrectionMetaData,
+    mask: Option<usize>,
+}
+
+impl MicroQRCode {
+    // encodes all of the data in the single mode that can represent every character, in the smallest of M1 to M4
+    // micro symbols do not support level H, M1 only supports level L
+    pub fn new(data: String, error_correction: ErrorCorrectionLevel) -> Result<Self, String> {
+        if error_correction == ErrorCorrectionLevel::H {
+            return Err(String::from("Micro QR codes do not support the error correction level H!"))
+        }
+
+        let segment = Segment::new(find_encoding(&data), data.clone());
+        let version = get_micro_size(segment.char_count(), &segment.encoding, &error_correction);
+
+        // M and Q are only offered by the larger versions, so the data may still fit with a lower level
+        if version == 0 && get_micro_size(segment.char_count(), &segment.encoding, &ErrorCorrectionLevel::L) > 0 {
+            return Err(format!("No micro version holding the data supports the error correction level {:?}!", error_correction))
+        }
+        if version == 0 {
+            return Err(String::from("Data too long for micro encoding!"))
+        }
+
+        let err_metadata = get_micro_err_metadata(version, &error_correction).unwrap();
+        Ok(MicroQRCode {
+            raw_data: data,
+            segment,
+            error_correction,
+            version,
+            err_metadata,
+            mask: None,
+        })
+    }
+
+    pub fn raw_data(&self) -> &str {
+        &self.raw_data
+    }
+
+    pub fn segment(&self) -> &Segment {
+        &self.segment
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> &ErrorCorrectionLevel {
+        &self.error_correction
+    }
+
+    // M1 and M3 end their data with a codeword of only 4 bits
+    fn has_half_codeword(&self) -> bool {
+        self.err_metadata.data_bits % 8 == 4
+    }
+
+    pub fn encode(&self) -> Result<String, QRCodeError> {
+        let mut bit_buffer = String::new();
+
+        bit_buffer.push_str(&get_micro_encoding(&self.segment.encoding, self.version)?);
+
+        let width = get_micro_count_bits(&self.segment.encoding, self.version)?;
+        bit_buffer.push_str(&format!("{:0width$b}", self.segment.char_count(), width = width));
+
+        encode_data(&self.segment, &mut bit_buffer);
+
+        self.add_padding(&mut bit_buffer);
+
+        Ok(bit_buffer)
+    }
+
+    fn add_padding(&self, bit_buffer: &mut String) {
+        let bit_size = self.err_metadata.data_bits;
+
+        // the terminator is 3, 5, 7 or 9 0s and may be cut short by the end of the data region
+        let terminator_len = (self.version * 2 + 1).min(bit_size - bit_buffer.len());
+        for _ in 0..terminator_len { bit_buffer.push('0'); }
+
+        // pad to multiple of 8, or to the end if the data reached the final 4 bit codeword
+        let diff_to_eight = match bit_buffer.len() % 8 {
+            0 => 0,
+            val => (8 - val).min(bit_size - bit_buffer.len()),
+        };
+        for _ in 0..diff_to_eight { bit_buffer.push('0'); }
+
+        // add padding bytes, the final 4 bit codeword is left as 0s
+        let missing_bytes = (bit_size - bit_buffer.len()) / 8;
+        let padding_bytes = ["11101100", "00010001"];
+        for i in 0..missing_bytes {
+            bit_buffer.push_str(padding_bytes[i % 2]);
+        }
+
+        for _ in bit_buffer.len()..bit_size { bit_buffer.push('0'); }
+    }
+
+    // the final 4 bit codeword of M1 and M3 is stored in the high bits of its byte
+    pub fn get_data_codewords(&self) -> Result<Vec<u8>, QRCodeError> {
+        let mut bitbuf = self.encode()?;
+        if self.has_half_codeword() { bitbuf.push_str("0000"); }
+
+        let chunks = bitbuf.len() / 8;
+
+        let mut codewords = vec![0; chunks];
+        for (i, codeword) in codewords.iter_mut().enumerate() {
+            let byte = &bitbuf[i*8..(i + 1)*8];
+            *codeword = u8::from_str_radix(byte, 2)
+                                .expect("This should be a valid bin string!");
+        }
+
+        Ok(codewords)
+    }
+
+    pub fn gen_error_codewords(&self) -> Result<Vec<u8>, QRCodeError> {
+        let code = ReedSolomon::new(self.err_metadata.words_per_block);
+        Ok(code.ec_codewords(&self.get_data_codewords()?))
+    }
+
+    // data codewords followed by the error correction codewords, only 4 bits of a final half codeword are placed
+    pub fn final_message(&self) -> Result<String, QRCodeError> {
+        let mut message = self.encode()?;
+
+        for codeword in self.gen_error_codewords()? {
+            message.push_str(&format!("{:08b}", codeword));
+        }
+
+        Ok(message)
+    }
+
+    // forces one of the four micro mask patterns instead of choosing the one with the highest score
+    pub fn set_mask(&mut self, mask: usize) -> Result<(), QRCodeError> {
+        if mask >= NUM_MICRO_MASKS {
+            return Err(QRCodeError::InvalidMask);
+        }
+
+        self.mask = Some(mask);
+        Ok(())
+    }
+
+    // finished symbol grid with the finder and timing patterns, the masked final message and the format information
+    pub fn gen_matrix(&self) -> Result<QRMatrix, QRCodeError> {
+        let mut matrix = QRMatrix::new_micro(self.version);
+        matrix.place_data(&self.final_message()?);
+
+        let mask = match self.mask {
+            Some(mask) => mask,
+            None => (0..NUM_MICRO_MASKS).min_by_key(|&mask| {
+                let mut candidate = matrix.clone();
+                candidate.apply_mask(mask);
+                Reverse(candidate.micro_score())
+            }).unwrap(),
+        };
+        matrix.apply_mask(mask);
+        matrix.place_micro_format_info(self.err_metadata.symbol_number, mask);
+
+        Ok(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(data: &str, error_correction: ErrorCorrectionLevel) -> Vec<String> {
+        let matrix = MicroQRCode::new(String::from(data), error_correction).unwrap().gen_matrix().unwrap();
+        (0..matrix.size())
+            .map(|y| (0..matrix.size()).map(|x| if matrix.get(x, y) { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    // the golden symbols were generated by the qrcode crate 0.14.1 (https://github.com/kennytm/qrcode-rust), which picks
+    // the same masks, M3-L is left out as that version does not follow the spec for it
+    #[test]
+    fn golden_m1_numeric() {
+        assert_eq!(rows("01234", ErrorCorrectionLevel::L), [
+            "#######.#.#",
+            "#.....#...#",
+            "#.###.#.###",
+            "#.###.#..#.",
+            "#.###.#....",
+            "#.....#.#.#",
+            "#######.#.#",
+            "........###",
+            "##.....#...",
+            "....###.#.#",
+            "###.#.####.",
+        ]);
+    }
+
+    #[test]
+    fn golden_m2_alphanumeric() {
+        assert_eq!(rows("HELLO", ErrorCorrectionLevel::M), [
+            "#######.#.#.#",
+            "#.....#..#...",
+            "#.###.#.#####",
+            "#.###.#..##.#",
+            "#.###.#.#..#.",
+            "#.....#..#..#",
+            "#######..####",
+            "........##...",
+            "###.#...#..##",
+            ".#.###.######",
+            "#.#.#.#....##",
+            ".#.#.#.#.###.",
+            "###..#...###.",
+        ]);
+    }
+
+    #[test]
+    fn golden_m3_byte() {
+        assert_eq!(rows("hello", ErrorCorrectionLevel::M), [
+            "#######.#.#.#.#",
+            "#.....#.##..#..",
+            "#.###.#...#...#",
+            "#.###.#..#####.",
+            "#.###.#.#..#..#",
+            "#.....#..#..#.#",
+            "#######.##....#",
+            "........#..###.",
+            "#.....######.##",
+            ".##........#.#.",
+            "#..#.#.#.###..#",
+            ".#.###.#####.#.",
+            "#.#...#####.#.#",
+            ".#####..#.#.#.#",
+            "#.#####.#..#..#",
+        ]);
+    }
+
+    #[test]
+    fn golden_m4_alphanumeric() {
+        assert_eq!(rows("MICRO QR M4", ErrorCorrectionLevel::Q), [
+            "#######.#.#.#.#.#",
+            "#.....#.#.##....#",
+            "#.###.#.#.#.##...",
+            "#.###.#...#.####.",
+            "#.###.#......#...",
+            "#.....#.....##...",
+            "#######.####.###.",
+            "........###.#.#..",
+            "#.##.#..#.##.#.##",
+            ".####..###...##.#",
+            "#..#...#..###.#..",
+            ".#.#.####.#..####",
+            "#.#.#...#######.#",
+            "..#####.###..####",
+            "####..#######.##.",
+            "..##.##.#.#.##..#",
+            "#...##..#########",
+        ]);
+    }
+
+    #[test]
+    fn smallest_version_holding_the_data_is_chosen() {
+        let version = |data: &str, error_correction| MicroQRCode::new(String::from(data), error_correction).map(|code| code.version());
+        let digits = |len: usize| "0123456789".repeat(4)[..len].to_string();
+
+        assert_eq!(version(&digits(5), ErrorCorrectionLevel::L), Ok(1));
+        assert_eq!(version(&digits(6), ErrorCorrectionLevel::L), Ok(2));
+        assert_eq!(version(&digits(10), ErrorCorrectionLevel::L), Ok(2));
+        assert_eq!(version(&digits(11), ErrorCorrectionLevel::L), Ok(3));
+        assert_eq!(version(&digits(23), ErrorCorrectionLevel::L), Ok(3));
+        assert_eq!(version(&digits(24), ErrorCorrectionLevel::L), Ok(4));
+        assert_eq!(version(&digits(35), ErrorCorrectionLevel::L), Ok(4));
+        assert_eq!(version(&digits(5), ErrorCorrectionLevel::M), Ok(2));
+        assert_eq!(version(&digits(21), ErrorCorrectionLevel::Q), Ok(4));
+
+        // M1 only holds numeric data
+        assert_eq!(version("A", ErrorCorrectionLevel::L), Ok(2));
+        assert!(get_micro_count_bits(&QREncoding::AlphaNumeric, 1).is_err());
+        assert!(get_micro_count_bits(&QREncoding::Byte, 2).is_err());
+        assert_eq!(version("a", ErrorCorrectionLevel::L), Ok(3));
+    }
+
+    #[test]
+    fn data_at_the_capacity_edges_fills_the_symbol() {
+        let cases = [
+            ("0".repeat(5), ErrorCorrectionLevel::L, 1),
+            ("A".repeat(6), ErrorCorrectionLevel::L, 2),
+            ("a".repeat(9), ErrorCorrectionLevel::L, 3),
+            ("0".repeat(35), ErrorCorrectionLevel::L, 4),
+            ("A".repeat(18), ErrorCorrectionLevel::M, 4),
+            ("a".repeat(9), ErrorCorrectionLevel::Q, 4),
+        ];
+
+        for (data, error_correction, expected) in cases.iter() {
+            let code = MicroQRCode::new(data.clone(), *error_correction).unwrap();
+            assert_eq!(code.version(), *expected);
+            assert_eq!(code.encode().unwrap().len(), code.err_metadata.data_bits);
+            assert!(code.gen_matrix().is_ok());
+        }
+    }
+
+    #[test]
+    fn unsupported_levels_are_reported() {
+        let level_h = MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::H).unwrap_err();
+        assert_eq!(level_h, "Micro QR codes do not support the error correction level H!");
+
+        let level_q = MicroQRCode::new("0".repeat(22), ErrorCorrectionLevel::Q).unwrap_err();
+        assert_eq!(level_q, "No micro version holding the data supports the error correction level Q!");
+
+        let too_long = MicroQRCode::new("0".repeat(36), ErrorCorrectionLevel::L).unwrap_err();
+        assert_eq!(too_long, "Data too long for micro encoding!");
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+use std::ops::{Add, Sub, Mul};
+
+use crate::gf256::{Gf, QR_PRIMITIVE_POLYNOMIAL};
+
+// polynomial over GF(2^8), the coefficients are stored lowest power of x first
+// trailing zero coefficients are always removed, so the zero polynomial has no coefficients and no degree
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Poly<const POLY: u16 = QR_PRIMITIVE_POLYNOMIAL> {
+    coeffs: Vec<Gf<POLY>>,
+}
+
+impl<const POLY: u16> Poly<POLY> {
+    pub fn new(mut coeffs: Vec<Gf<POLY>>) -> Self {
+        while coeffs.last().is_some_and(|coeff| coeff.is_zero()) { coeffs.pop(); }
+        Self { coeffs }
+    }
+
+    pub fn zero() -> Self {
+        Self { coeffs: Vec::new() }
+    }
+
+    // coeff * x^degree
+    pub fn monomial(coeff: Gf<POLY>, degree: usize) -> Self {
+        let mut coeffs = vec![Gf::ZERO; degree + 1];
+        coeffs[degree] = coeff;
+        Self::new(coeffs)
+    }
+
+    // codewords are read highest power of x first, like they are sent
+    pub fn from_codewords(codewords: &[u8]) -> Self {
+        Self::new(codewords.iter().rev().map(|&codeword| Gf::from(codeword)).collect())
+    }
+
+    pub fn coeffs(&self) -> &[Gf<POLY>] {
+        &self.coeffs
+    }
+
+    // coefficient of x^power, zero above the degree
+    pub fn coeff(&self, power: usize) -> Gf<POLY> {
+        self.coeffs.get(power).copied().unwrap_or(Gf::ZERO)
+    }
+
+    // None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    pub fn scale(&self, factor: Gf<POLY>) -> Self {
+        Self::new(self.coeffs.iter().map(|&coeff| coeff * factor).collect())
+    }
+
+    // multiplies by x^power
+    pub fn shift(&self, power: usize) -> Self {
+        if self.is_zero() { return Self::zero(); }
+
+        let mut coeffs = vec![Gf::ZERO; power];
+        coeffs.extend_from_slice(&self.coeffs);
+        Self { coeffs }
+    }
+
+    // value at x by Horner's method
+    pub fn eval(&self, x: Gf<POLY>) -> Gf<POLY> {
+        self.coeffs.iter().rev().fold(Gf::ZERO, |acc, &coeff| acc * x + coeff)
+    }
+
+    // formal derivative, i * a is a if i is odd and 0 if it is even in characteristic 2
+    pub fn derivative(&self) -> Self {
+        Self::new(self.coeffs.iter().enumerate().skip(1)
+            .map(|(power, &coeff)| if power % 2 == 1 { coeff } else { Gf::ZERO })
+            .collect())
+    }
+
+    // quotient and remainder of the long division, panics if the divisor is zero
+    pub fn divmod(&self, divisor: &Self) -> (Self, Self) {
+        let divisor_degree = divisor.degree().expect("Division by the zero polynomial!");
+        let leading_inv = divisor.coeffs[divisor_degree].inv();
+
+        let mut remainder = self.coeffs.clone();
+        let quotient_len = (remainder.len() + 1).saturating_sub(divisor.coeffs.len());
+        let mut quotient = vec![Gf::ZERO; quotient_len];
+
+        // cancel the highest remaining coefficient with a multiple of the divisor, from the top down
+        for power in (0..quotient_len).rev() {
+            let factor = remainder[power + divisor_degree] * leading_inv;
+            if factor.is_zero() { continue; }
+
+            quotient[power] = factor;
+            for (i, &coeff) in divisor.coeffs.iter().enumerate() {
+                remainder[power + i] -= coeff * factor;
+            }
+        }
+
+        remainder.truncate(divisor_degree);
+        (Self::new(quotient), Self::new(remainder))
+    }
+}
+
+impl<const POLY: u16> Add for &Poly<POLY> {
+    type Output = Poly<POLY>;
+
+    fn add(self, rhs: Self) -> Poly<POLY> {
+        let len = self.coeffs.len().max(rhs.coeffs.len());
+        Poly::new((0..len).map(|power| self.coeff(power) + rhs.coeff(power)).collect())
+    }
+}
+
+impl<const POLY: u16> Sub for &Poly<POLY> {
+    type Output = Poly<POLY>;
+
+    // subtraction is the same as addition in characteristic 2
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Self) -> Poly<POLY> {
+        self + rhs
+    }
+}
+
+impl<const POLY: u16> Mul for &Poly<POLY> {
+    type Output = Poly<POLY>;
+
+    fn mul(self, rhs: Self) -> Poly<POLY> {
+        if self.is_zero() || rhs.is_zero() { return Poly::zero(); }
+
+        let mut coeffs = vec![Gf::ZERO; self.coeffs.len() + rhs.coeffs.len() - 1];
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            for (j, &b) in rhs.coeffs.iter().enumerate() {
+                coeffs[i + j] += a * b;
+            }
+        }
+
+        Poly::new(coeffs)
+    }
+}
+
+impl<const POLY: u16> Add for Poly<POLY> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        &self + &rhs
+    }
+}
+
+impl<const POLY: u16> Sub for Poly<POLY> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        &self - &rhs
+    }
+}
+
+impl<const POLY: u16> Mul for Poly<POLY> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
+    }
+}
+
+// highest power first, like 1 * x^2 + 3 * x^1 + 2
+impl<const POLY: u16> fmt::Display for Poly<POLY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() { return write!(f, "0"); }
+
+        let terms: Vec<String> = self.coeffs.iter().enumerate().rev()
+            .filter(|(_, coeff)| !coeff.is_zero())
+            .map(|(power, coeff)| if power == 0 { format!("{}", coeff.0) } else { format!("{} * x^{}", coeff.0, power) })
+            .collect();
+        write!(f, "{}", terms.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gf256::Gf256;
+
+    // coefficients lowest power of x first
+    fn poly(coeffs: &[u8]) -> Poly {
+        Poly::new(coeffs.iter().map(|&coeff| Gf::from(coeff)).collect())
+    }
+
+    #[test]
+    fn leading_zeros_do_not_count_for_the_degree() {
+        assert_eq!(Poly::<QR_PRIMITIVE_POLYNOMIAL>::zero().degree(), None);
+        assert_eq!(poly(&[0, 0, 0]).degree(), None);
+        assert!(poly(&[0, 0, 0]).is_zero());
+        assert_eq!(poly(&[7]).degree(), Some(0));
+        assert_eq!(poly(&[1, 2, 0, 0]).degree(), Some(1));
+        assert_eq!(Poly::<QR_PRIMITIVE_POLYNOMIAL>::from_codewords(&[0, 0, 5, 7]), poly(&[7, 5]));
+        assert_eq!(Poly::monomial(Gf256::ZERO, 3).degree(), None);
+        assert_eq!(Poly::monomial(Gf256::ONE, 3).degree(), Some(3));
+    }
+
+    #[test]
+    fn division_leaves_a_remainder_of_lower_degree() {
+        let dividends = [poly(&[]), poly(&[9]), poly(&[1, 2, 3, 4, 5, 6, 7, 8]), poly(&[0, 0, 0, 0, 0, 0xAB]), poly(&[255; 20])];
+        let divisors = [poly(&[3]), poly(&[1, 1]), poly(&[2, 0, 7]), poly(&[5, 4, 3, 2, 1, 0x80]), poly(&[1; 12])];
+
+        for dividend in dividends.iter() {
+            for divisor in divisors.iter() {
+                let (quotient, remainder) = dividend.divmod(divisor);
+                assert_eq!(&(&quotient * divisor) + &remainder, *dividend, "{} / {}", dividend, divisor);
+                assert!(remainder.degree() < divisor.degree());
+            }
+        }
+
+        // (x + 1) * (x + 2) = x^2 + 3x + 2
+        assert_eq!(poly(&[2, 3, 1]).divmod(&poly(&[1, 1])), (poly(&[2, 1]), Poly::zero()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Division by the zero polynomial!")]
+    fn division_by_zero_panics() {
+        poly(&[1, 2]).divmod(&Poly::zero());
+    }
+
+    // the even powers vanish as 2 * a = a + a = 0, the odd ones keep their coefficient
+    #[test]
+    fn derivative_in_characteristic_two() {
+        assert_eq!(poly(&[1, 2, 3, 4, 5, 6]).derivative(), poly(&[2, 0, 4, 0, 6]));
+        assert_eq!(poly(&[7, 0, 9]).derivative(), Poly::zero());
+        assert_eq!(poly(&[7]).derivative(), Poly::zero());
+        assert_eq!(Poly::<QR_PRIMITIVE_POLYNOMIAL>::zero().derivative(), Poly::zero());
+    }
+
+    #[test]
+    fn evaluation_at_points() {
+        let p = poly(&[2, 3, 1]);
+        assert_eq!(p.eval(Gf::from(1)), Gf::ZERO);
+        assert_eq!(p.eval(Gf::from(2)), Gf::ZERO);
+        assert_eq!(p.eval(Gf::ZERO), Gf::from(2));
+        assert_eq!(Poly::<QR_PRIMITIVE_POLYNOMIAL>::zero().eval(Gf::from(5)), Gf::ZERO);
+
+        // at 1 the value is the sum of the coefficients, elsewhere the sum of the terms
+        let q = poly(&[0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(q.eval(Gf::ONE), Gf::from(0x12 ^ 0x34 ^ 0x56 ^ 0x78));
+        for value in [2u8, 0x53, 0xFF] {
+            let x = Gf256::from(value);
+            let terms = q.coeffs().iter().enumerate().fold(Gf::ZERO, |sum, (power, &coeff)| sum + coeff * x.pow(power as u32));
+            assert_eq!(q.eval(x), terms);
+        }
+    }
+}
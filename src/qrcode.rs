@@ -1,6 +1,12 @@
 use regex::Regex;
+use encoding_rs::SHIFT_JIS;
 
-use crate::solomon_reed::{get_generator_polynomial, nums_to_coeffs, mult_by_pow_of_x, get_poly_degree, pretty_print_polynomial_ints, get_code_words};
+use crate::eci::{Charset, encode_eci_designator};
+use crate::matrix::{QRMatrix, NUM_MASKS};
+use crate::gs1::parse_element_string;
+use crate::segment::{Segment, needs_eci_header, optimal_segments};
+
+use crate::solomon_reed::ReedSolomon;
 
 // source: https://www.thonky.com/qr-code-tutorial/
 
@@ -8,17 +14,42 @@ use crate::solomon_reed::{get_generator_polynomial, nums_to_coeffs, mult_by_pow_
 pub enum QRCodeError {
     UnsupportedVersion,
     DataSizeMissing,
+    InvalidMask,
+    UnsupportedCharacter,
 }
 
-#[derive(Debug, PartialEq)]
-enum QREncoding {
+#[derive(Debug, Clone, PartialEq)]
+pub enum QREncoding {
     Numeric,
     AlphaNumeric,
     Byte,
-    _StructuredAppendMode,
+    Kanji,
+    Eci(u32),
+    StructuredAppend { index: u8, total: u8, parity: u8 },
+    Fnc1First,
+    Fnc1Second(u8),
+}
+
+// FNC1 in first position marks GS1 data, in second position data of the AIM application with the given indicator
+// the indicator is either a two digit number or a letter plus 100
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fnc1 {
+    FirstPosition,
+    SecondPosition(u8),
+}
+
+// separates variable length GS1 fields, written as % by alphanumeric segments in FNC1 mode
+pub const GROUP_SEPARATOR: char = '\u{1D}';
+
+// settings and header segments that apply to the whole symbol
+#[derive(Debug, Clone, Default)]
+struct EncodingOptions {
+    charset: Charset,
+    structured_append: Option<Segment>,
+    fnc1: Option<Fnc1>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ErrorCorrectionLevel {
     L,
     M,
@@ -26,42 +57,75 @@ pub enum ErrorCorrectionLevel {
     H,
 }
 
+impl ErrorCorrectionLevel {
+    // the two bit indicator of the level used in the format information
+    pub fn format_bits(&self) -> u32 {
+        match self {
+            ErrorCorrectionLevel::L => 0b01,
+            ErrorCorrectionLevel::M => 0b00,
+            ErrorCorrectionLevel::Q => 0b11,
+            ErrorCorrectionLevel::H => 0b10,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct QRCode {
     raw_data: String,
-    encoding: QREncoding,
+    segments: Vec<Segment>,
     error_correction: ErrorCorrectionLevel,
     version: usize,
     err_metadata: ErrorCorrectionMetaData,
+    mask: Option<usize>,
 }
 
-fn find_encoding(data: &str) -> QREncoding {
+// Shift JIS value of the character if it is one of the double byte characters Kanji mode can encode
+pub(crate) fn get_kanji_sjis(c: char) -> Option<u16> {
+    let mut utf8_buf = [0u8; 4];
+    let (bytes, _, had_errors) = SHIFT_JIS.encode(c.encode_utf8(&mut utf8_buf));
+    if had_errors || bytes.len() != 2 { return None; }
+
+    let value = u16::from_be_bytes([bytes[0], bytes[1]]);
+    if (0x8140..=0x9FFC).contains(&value) || (0xE040..=0xEBBF).contains(&value) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn find_encoding(data: &str) -> QREncoding {
     let numeric_regex      = Regex::new(r"^[0-9]*$").unwrap();
-    let alphanumeric_regex = Regex::new(r"^[0-9A-Z\s$%\*\+-\./:]*$").unwrap();
+    let alphanumeric_regex = Regex::new(r"^[0-9A-Z $%\*\+\-\./:]*$").unwrap();
 
     if numeric_regex.is_match(data) {
         QREncoding::Numeric
     } else if alphanumeric_regex.is_match(data) {
         QREncoding::AlphaNumeric
+    } else if data.chars().all(|c| get_kanji_sjis(c).is_some()) {
+        QREncoding::Kanji
     } else {
         QREncoding::Byte
     }
 }
 
-const CHAR_CAPACITY: [(QREncoding, ErrorCorrectionLevel, [usize; 40]); 12]
+const CHAR_CAPACITY: [(QREncoding, ErrorCorrectionLevel, [usize; 40]); 16]
     = [
         (QREncoding::Numeric,       ErrorCorrectionLevel::L, [41,77,127,187,255,322,370,461,552,652,772,883,1022,1101,1250,1408,1548,1725,1903,2061,2232,2409,2620,2812,3057,3283,3517,3669,3909,4158,4417,4686,4965,5253,5529,5836,6153,6479,6743,7089]),
         (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::L, [25,47,77,114,154,195,224,279,335,395,468,535,619,667,758,854,938,1046,1153,1249,1352,1460,1588,1704,1853,1990,2132,2223,2369,2520,2677,2840,3009,3183,3351,3537,3729,3927,4087,4296]),
         (QREncoding::Byte,          ErrorCorrectionLevel::L, [17,32,53,78,106,134,154,192,230,271,321,367,425,458,520,586,644,718,792,858,929,1003,1091,1171,1273,1367,1465,1528,1628,1732,1840,1952,2068,2188,2303,2431,2563,2699,2809,2953]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::L, [10,20,32,48,65,82,95,118,141,167,198,226,262,282,320,361,397,442,488,528,572,618,672,721,784,842,902,940,1002,1066,1132,1201,1273,1347,1417,1496,1577,1661,1729,1817]),
         (QREncoding::Numeric,       ErrorCorrectionLevel::M, [34,63,101,149,202,255,293,365,432,513,604,691,796,871,991,1082,1212,1346,1500,1600,1708,1872,2059,2188,2395,2544,2701,2857,3035,3289,3486,3693,3909,4134,4343,4588,4775,5039,5313,5596]),
         (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::M, [20,38,61,90,122,154,178,221,262,311,366,419,483,528,600,656,734,816,909,970,1035,1134,1248,1326,1451,1542,1637,1732,1839,1994,2113,2238,2369,2506,2632,2780,2894,3054,3220,3391]),
         (QREncoding::Byte,          ErrorCorrectionLevel::M, [14,26,42,62,84,106,122,152,180,213,251,287,331,362,412,450,504,560,624,666,711,779,857,911,997,1059,1125,1190,1264,1370,1452,1538,1628,1722,1809,1911,1989,2099,2213,2331]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::M, [8,16,26,38,52,65,75,93,111,131,155,177,204,223,254,277,310,345,384,410,438,480,528,561,614,652,692,732,778,843,894,947,1002,1060,1113,1176,1224,1292,1362,1435]),
         (QREncoding::Numeric,       ErrorCorrectionLevel::Q, [27,48,77,111,144,178,207,259,312,364,427,489,580,621,703,775,876,948,1063,1159,1224,1358,1468,1588,1718,1804,1933,2085,2181,2358,2473,2670,2805,2949,3081,3244,3417,3599,3791,3993]),
         (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::Q, [16,29,47,67,87,108,125,157,189,221,259,296,352,376,426,470,531,574,644,702,742,823,890,963,1041,1094,1172,1263,1322,1429,1499,1618,1700,1787,1867,1966,2071,2181,2298,2420]),
         (QREncoding::Byte,          ErrorCorrectionLevel::Q, [11,20,32,46,60,74,86,108,130,151,177,203,241,258,292,322,364,394,442,482,509,565,611,661,715,751,805,868,908,982,1030,1112,1168,1228,1283,1351,1423,1499,1579,1663]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::Q, [7,12,20,28,37,45,53,66,80,93,109,125,149,159,180,198,224,243,272,297,314,348,376,407,440,462,496,534,559,604,634,684,719,756,790,832,876,923,972,1024]),
         (QREncoding::Numeric,       ErrorCorrectionLevel::H, [17,34,58,82,106,139,154,202,235,288,331,374,427,468,530,602,674,746,813,919,969,1056,1108,1228,1286,1425,1501,1581,1677,1782,1897,2022,2157,2301,2361,2524,2625,2735,2927,3057]),
         (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::H, [10,20,35,50,64,84,93,122,143,174,200,227,259,283,321,365,408,452,493,557,587,640,672,744,779,864,910,958,1016,1080,1150,1226,1307,1394,1431,1530,1591,1658,1774,1852]),
         (QREncoding::Byte,          ErrorCorrectionLevel::H, [7,14,24,34,44,58,64,84,98,119,137,155,177,194,220,250,280,310,338,382,403,439,461,511,535,593,625,658,698,742,790,842,898,958,983,1051,1093,1139,1219,1273]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::H, [4,8,15,21,27,36,39,52,60,74,85,96,109,120,136,154,173,191,208,235,248,270,284,315,330,365,385,405,430,457,486,518,553,590,605,647,673,701,750,784]),
     ];
 
 // looks up char capacity per version depending on the encoding and error correction level
@@ -70,23 +134,23 @@ fn get_size(data_size: usize, encoding: &QREncoding, error_correction: &ErrorCor
 
     for option in CHAR_CAPACITY {
         if &option.0 == current_option.0 && &option.1 == current_option.1 {
-            for i in 0..20 {
+            for i in 0..40 {
                 let size = option.2[i];
                 if data_size <= size { return i + 1 }
             }
         }
     }
 
-    return 0;
+    0
 }
 
-fn get_size_from_version(version: usize) -> usize {
-    version * 4 + 21
+pub fn get_size_from_version(version: usize) -> usize {
+    (version - 1) * 4 + 21
 }
 
 #[derive(Debug)]
-struct ErrorCorrectionMetaData {
-    words_per_block: usize,
+pub(crate) struct ErrorCorrectionMetaData {
+    pub(crate) words_per_block: usize,
     blocks_grp1: usize,
     blocks_grp2: usize,
     words_per_block_grp1: usize,
@@ -99,7 +163,11 @@ impl ErrorCorrectionMetaData {
     }
 
     pub fn total_code_words(&self) -> usize {
-        return self.blocks_grp1 * self.words_per_block_grp1 + self.blocks_grp2 * self.words_per_block_grp2
+        self.blocks_grp1 * self.words_per_block_grp1 + self.blocks_grp2 * self.words_per_block_grp2
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks_grp1 + self.blocks_grp2
     }
 }
 
@@ -281,7 +349,7 @@ const ERROR_CORRECTION_DATA: [(usize, ErrorCorrectionLevel, [usize; 5]); 160] =
     (40, ErrorCorrectionLevel::H, [30,20,15,61,16]),
 ];
 
-fn get_err_metadata(version: usize, error_correction: &ErrorCorrectionLevel) -> Option<ErrorCorrectionMetaData> {
+pub(crate) fn get_err_metadata(version: usize, error_correction: &ErrorCorrectionLevel) -> Option<ErrorCorrectionMetaData> {
     let current_option = (version, error_correction);
     for option in ERROR_CORRECTION_DATA.iter() {
         if option.0 == current_option.0 && &option.1 == current_option.1 {
@@ -292,19 +360,88 @@ fn get_err_metadata(version: usize, error_correction: &ErrorCorrectionLevel) ->
     None
 }
 
+// splits the data codewords into the blocks of group 1 followed by the blocks of group 2
+pub(crate) fn split_blocks(codewords: &[u8], meta: &ErrorCorrectionMetaData) -> Vec<Vec<u8>> {
+    let block_sizes = std::iter::repeat_n(meta.words_per_block_grp1, meta.blocks_grp1)
+        .chain(std::iter::repeat_n(meta.words_per_block_grp2, meta.blocks_grp2));
+
+    let mut blocks = Vec::with_capacity(meta.blocks_grp1 + meta.blocks_grp2);
+    let mut offset = 0;
+    for size in block_sizes {
+        blocks.push(codewords[offset..offset + size].to_vec());
+        offset += size;
+    }
+
+    blocks
+}
+
+// error correction codewords for each data block, all blocks share the same number of them
+pub(crate) fn gen_block_error_codewords(blocks: &[Vec<u8>], words_per_block: usize) -> Vec<Vec<u8>> {
+    let code = ReedSolomon::new(words_per_block);
+    blocks.iter().map(|block| code.ec_codewords(block)).collect()
+}
+
+// interleaved data codewords followed by the interleaved error correction codewords
+pub(crate) fn interleave_blocks(data_blocks: &[Vec<u8>], error_blocks: &[Vec<u8>]) -> String {
+    let mut message = String::new();
+
+    // take the i-th codeword of every block in turn, the longer blocks of group 2 supply the last column alone
+    let max_data_len = data_blocks.iter().map(|block| block.len()).max().unwrap_or(0);
+    for i in 0..max_data_len {
+        for block in data_blocks.iter().filter(|block| i < block.len()) {
+            message.push_str(&format!("{:08b}", block[i]));
+        }
+    }
+
+    let words_per_block = error_blocks.first().map_or(0, |block| block.len());
+    for i in 0..words_per_block {
+        for block in error_blocks.iter() {
+            message.push_str(&format!("{:08b}", block[i]));
+        }
+    }
+
+    message
+}
+
+// block of every codeword of the final message, in the order interleave_blocks writes them
+pub(crate) fn interleaved_block_indices(meta: &ErrorCorrectionMetaData) -> Vec<usize> {
+    let block_sizes: Vec<usize> = std::iter::repeat_n(meta.words_per_block_grp1, meta.blocks_grp1)
+        .chain(std::iter::repeat_n(meta.words_per_block_grp2, meta.blocks_grp2))
+        .collect();
+
+    let max_data_len = block_sizes.iter().copied().max().unwrap_or(0);
+    let mut indices: Vec<usize> = (0..max_data_len)
+        .flat_map(|i| block_sizes.iter().enumerate().filter(move |(_, &size)| i < size).map(|(block, _)| block))
+        .collect();
+    for _ in 0..meta.words_per_block {
+        indices.extend(0..block_sizes.len());
+    }
+
+    indices
+}
+
+// a structured append message is split over at most 16 symbols, as the index only has 4 bits
+pub const MAX_STRUCTURED_APPEND_SYMBOLS: usize = 16;
+
+// number of 0 bits appended after the final codewords to fill up the data region of each version
+// source: https://www.thonky.com/qr-code-tutorial/structure-final-message
+pub(crate) const REMAINDER_BITS: [usize; 40] = [
+    0, 7, 7, 7, 7, 7, 0, 0, 0, 0, 0, 0, 0, 3, 3, 3, 3, 3, 3, 3,
+    4, 4, 4, 4, 4, 4, 4, 3, 3, 3, 3, 3, 3, 3, 0, 0, 0, 0, 0, 0,
+];
+
 fn encode_numeric(bit_buffer: &mut String, current_slice: &str) {
     let parsed_int: i32 = current_slice.parse().unwrap();
 
-    if parsed_int > 100 {
-        bit_buffer.push_str(&format!("{:010b}", parsed_int));
-    } else if parsed_int < 100 && parsed_int >= 10 {
-        bit_buffer.push_str(&format!("{:07b}", parsed_int));
-    } else {
-        bit_buffer.push_str(&format!("{:04b}", parsed_int));
+    // groups of 3, 2 and 1 digits are encoded with 10, 7 and 4 bits
+    match current_slice.len() {
+        3 => bit_buffer.push_str(&format!("{:010b}", parsed_int)),
+        2 => bit_buffer.push_str(&format!("{:07b}", parsed_int)),
+        _ => bit_buffer.push_str(&format!("{:04b}", parsed_int)),
     };
 }
 
-fn alphanumeric_get_char_code(c: char) -> u16 {
+pub(crate) fn alphanumeric_get_char_code(c: char) -> u16 {
     match c {
     '0' => 0, '1' => 1, '2' => 2, '3' => 3, '4' => 4, '5' => 5,
     '6' => 6, '7' => 7, '8' => 8, '9' => 9, 'A' => 10, 'B' => 11, 
@@ -330,41 +467,71 @@ fn encode_alphanumeric(current_slice: &str) -> String {
     }
 }
 
-fn get_encoding(qrcode: &QRCode) -> &'static str {
+// subtracts the range offset from the Shift JIS value and packs its two bytes into 13 bits
+fn encode_kanji(c: char) -> String {
+    let sjis = get_kanji_sjis(c).expect("Character can not be encoded in Kanji mode!");
+    let offset = if sjis <= 0x9FFC { sjis - 0x8140 } else { sjis - 0xC140 };
+
+    let code = (offset >> 8) * 0xC0 + (offset & 0xFF);
+    format!("{:013b}", code)
+}
+
+fn get_encoding(segment: &Segment) -> &'static str {
     // add magic number for encoding type
-    match qrcode.encoding {
+    match segment.encoding {
         QREncoding::Numeric      => "0001",
         QREncoding::AlphaNumeric => "0010",
         QREncoding::Byte         => "0100",
-        _                        => "0000",
+        QREncoding::Kanji        => "1000",
+        QREncoding::Eci(_)       => "0111",
+        QREncoding::StructuredAppend { .. } => "0011",
+        QREncoding::Fnc1First    => "0101",
+        QREncoding::Fnc1Second(_) => "1001",
     }
 }
 
-fn get_data_len(qrcode: &QRCode) -> Result<String, QRCodeError> {
-    // lookup length of data 0 padded to specific lenght
-    Ok(match qrcode.version {
-        1..=9 => match qrcode.encoding {
-            QREncoding::Numeric      => format!("{:010b}", qrcode.raw_data.len()),
-            QREncoding::AlphaNumeric => format!("{:09b}",  qrcode.raw_data.len()),
-            QREncoding::Byte         => format!("{:08b}",  qrcode.raw_data.len()),
-            _                        => "0000".to_owned(),
+// length of the character count indicator, depends on the encoding and the version
+pub(crate) fn get_count_bits(encoding: &QREncoding, version: usize) -> Result<usize, QRCodeError> {
+    Ok(match version {
+        1..=9 => match encoding {
+            QREncoding::Numeric      => 10,
+            QREncoding::AlphaNumeric => 9,
+            QREncoding::Byte         => 8,
+            QREncoding::Kanji        => 8,
+            QREncoding::Eci(_)       => 0,
+            QREncoding::StructuredAppend { .. } => 0,
+            QREncoding::Fnc1First | QREncoding::Fnc1Second(_) => 0,
         },
-        10..=26 => match qrcode.encoding {
-            QREncoding::Numeric      => format!("{:012b}",  qrcode.raw_data.len()),
-            QREncoding::AlphaNumeric => format!("{:011b}",  qrcode.raw_data.len()),
-            QREncoding::Byte         => format!("{:016b}",  qrcode.raw_data.len()),
-            _                        => "0000".to_owned(),
+        10..=26 => match encoding {
+            QREncoding::Numeric      => 12,
+            QREncoding::AlphaNumeric => 11,
+            QREncoding::Byte         => 16,
+            QREncoding::Kanji        => 10,
+            QREncoding::Eci(_)       => 0,
+            QREncoding::StructuredAppend { .. } => 0,
+            QREncoding::Fnc1First | QREncoding::Fnc1Second(_) => 0,
         },
-        27..=40 => match qrcode.encoding {
-            QREncoding::Numeric      => format!("{:014b}",  qrcode.raw_data.len()),
-            QREncoding::AlphaNumeric => format!("{:013b}",  qrcode.raw_data.len()),
-            QREncoding::Byte         => format!("{:016b}",  qrcode.raw_data.len()),
-            _                        => "0000".to_owned(),
+        27..=40 => match encoding {
+            QREncoding::Numeric      => 14,
+            QREncoding::AlphaNumeric => 13,
+            QREncoding::Byte         => 16,
+            QREncoding::Kanji        => 12,
+            QREncoding::Eci(_)       => 0,
+            QREncoding::StructuredAppend { .. } => 0,
+            QREncoding::Fnc1First | QREncoding::Fnc1Second(_) => 0,
         },
         _ => { return Err(QRCodeError::UnsupportedVersion); }
     })
 }
 
+fn get_data_len(segment: &Segment, version: usize) -> Result<String, QRCodeError> {
+    // lookup length of data 0 padded to specific lenght, ECI segments have no character count
+    let width = get_count_bits(&segment.encoding, version)?;
+    if width == 0 { return Ok(String::new()); }
+
+    Ok(format!("{:0width$b}", segment.char_count(), width = width))
+}
+
 fn add_padding(qrcode: &QRCode, bit_buffer: &mut String) -> Result<(), QRCodeError> {
     // get the maximum number of bits
     let bit_size = qrcode.err_metadata.total_code_words() * 8;
@@ -383,109 +550,518 @@ fn add_padding(qrcode: &QRCode, bit_buffer: &mut String) -> Result<(), QRCodeErr
 
     // add padding to reach maximum data lenght
     let missing_bytes = (bit_size - bit_buffer.len()) / 8;
-    let padding_bytes = vec!["11101100", "00010001"];
+    let padding_bytes = ["11101100", "00010001"];
     for i in 0..missing_bytes {
         bit_buffer.push_str(padding_bytes[i % 2]);
     }
 
-    return Ok(());
+    Ok(())
 }
 
-fn encode_data(qrcode: &QRCode, bit_buffer: &mut String) {
-    match qrcode.encoding {
+pub(crate) fn encode_data(segment: &Segment, bit_buffer: &mut String) {
+    let data = &segment.data;
+
+    match segment.encoding {
         QREncoding::Numeric => {
-            let scaled_len = qrcode.raw_data.len() / 3;
-            let left_over  = qrcode.raw_data.len() % 3;
-        
+            let scaled_len = data.len() / 3;
+            let left_over  = data.len() % 3;
+
             for i in 0..scaled_len {
-                let current_slice = &qrcode.raw_data[i..i+3];
+                let current_slice = &data[i*3..(i+1)*3];
                 encode_numeric(bit_buffer, current_slice);
             }
-        
+
             if left_over > 0 {
-                encode_numeric(bit_buffer, &qrcode.raw_data[qrcode.raw_data.len() - left_over..qrcode.raw_data.len()]);
+                encode_numeric(bit_buffer, &data[data.len() - left_over..data.len()]);
             }
         },
         QREncoding::AlphaNumeric => {
-            let scaled_len = qrcode.raw_data.len() / 2;
-            let left_over  = qrcode.raw_data.len() % 2;
+            let scaled_len = data.len() / 2;
+            let left_over  = data.len() % 2;
 
             for i in 0..scaled_len {
-                let current_slice = &qrcode.raw_data[i*2..(i+1)*2];
+                let current_slice = &data[i*2..(i+1)*2];
                 let encoded = encode_alphanumeric(current_slice);
                 bit_buffer.push_str(&encoded);
             }
 
             if left_over > 0 {
-                let len = qrcode.raw_data.len();
-                let encoded = encode_alphanumeric(&qrcode.raw_data[len-1..len]);
+                let len = data.len();
+                let encoded = encode_alphanumeric(&data[len-1..len]);
                 bit_buffer.push_str(&encoded);
             }
         },
         QREncoding::Byte => {
-            let bytes = qrcode.raw_data.bytes();
-            bytes.for_each(|byte| {
+            let bytes = segment.bytes();
+            bytes.iter().for_each(|byte| {
                 bit_buffer.push_str(&format!("{:08b}", byte));
             });
+        },
+        QREncoding::Kanji => {
+            data.chars().for_each(|c| {
+                bit_buffer.push_str(&encode_kanji(c));
+            });
+        },
+        QREncoding::Eci(number) => {
+            bit_buffer.push_str(&encode_eci_designator(number).expect("Invalid ECI assignment number!"));
+        },
+        QREncoding::StructuredAppend { index, total, parity } => {
+            // the total is stored minus one so that 16 symbols fit into 4 bits
+            bit_buffer.push_str(&format!("{:04b}{:04b}{:08b}", index, total - 1, parity));
+        },
+        QREncoding::Fnc1First => { },
+        QREncoding::Fnc1Second(app_indicator) => {
+            bit_buffer.push_str(&format!("{:08b}", app_indicator));
+        },
+    }
+}
+
+// the parity is the xor of all data bytes as they are stored in the symbols, so Kanji counts with its Shift JIS bytes
+fn set_structured_append_parity(codes: &mut [QRCode]) {
+    let parity = codes.iter()
+        .flat_map(|code| code.segments.iter().flat_map(|segment| segment.message_bytes()))
+        .fold(0, |parity, byte| parity ^ byte);
+
+    for segment in codes.iter_mut().flat_map(|code| code.segments.iter_mut()) {
+        if let QREncoding::StructuredAppend { parity: header_parity, .. } = &mut segment.encoding {
+            *header_parity = parity;
         }
-        _ => { }
     }
 }
 
+fn segments_fit(segments: &[Segment], version: usize, error_correction: &ErrorCorrectionLevel) -> Result<bool, QRCodeError> {
+    let mut bits = 0;
+    for segment in segments.iter() {
+        bits += segment.total_bits(version)?;
+    }
+
+    let capacity = get_err_metadata(version, error_correction).unwrap().total_code_words() * 8;
+    Ok(bits <= capacity)
+}
+
+// puts the header segments in front of the data segments, in the order structured append, ECI, FNC1
+fn add_headers(data_segments: Vec<Segment>, options: &EncodingOptions) -> Vec<Segment> {
+    let mut segments = Vec::with_capacity(data_segments.len() + 3);
+
+    segments.extend(options.structured_append.clone());
+    if needs_eci_header(&data_segments) {
+        segments.push(Segment::eci(options.charset));
+    }
+    segments.extend(options.fnc1.map(Segment::fnc1));
+    segments.extend(data_segments);
+
+    segments
+}
+
+// picks the smallest version that fits the headers and the optimal segmentation of the data
+fn find_version(data: &str, error_correction: &ErrorCorrectionLevel, options: &EncodingOptions) -> Result<Option<(usize, Vec<Segment>)>, QRCodeError> {
+    // the segmentation only changes with the character count widths, so one run per version group is enough
+    for (first, last) in [(1, 9), (10, 26), (27, 40)] {
+        let data_segments = optimal_segments(data, last, options.charset, options.fnc1.is_some())?;
+        let segments = add_headers(data_segments, options);
+
+        for version in first..=last {
+            if segments_fit(&segments, version, error_correction)? {
+                return Ok(Some((version, segments)));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
 impl QRCode {
+    // splits the data into numeric, alphanumeric, byte and kanji segments with the shortest total bit stream
     pub fn new(data: String, error_correction: ErrorCorrectionLevel) -> Result<Self, String> {
+        Self::new_with_charset(data, error_correction, Charset::default())
+    }
+
+    // like new, but byte mode data is converted to the character set, announced by an ECI header if not plain ASCII
+    pub fn new_with_charset(data: String, error_correction: ErrorCorrectionLevel, charset: Charset) -> Result<Self, String> {
+        Self::new_with_options(data, error_correction, EncodingOptions { charset, ..Default::default() })
+    }
+
+    // like new, but the data is marked with an FNC1 header, group separators in the data separate GS1 fields
+    pub fn new_fnc1(data: String, error_correction: ErrorCorrectionLevel, fnc1: Fnc1) -> Result<Self, String> {
+        Self::new_with_options(data, error_correction, EncodingOptions { fnc1: Some(fnc1), ..Default::default() })
+    }
+
+    // validates a bracketed GS1 element string like (01)09501101530003(17)140704(10)AB-123 and encodes it in FNC1 first position
+    pub fn new_gs1(element_string: &str, error_correction: ErrorCorrectionLevel) -> Result<Self, String> {
+        let data = parse_element_string(element_string).map_err(|err| format!("{:?}", err))?;
+        Self::new_fnc1(data, error_correction, Fnc1::FirstPosition)
+    }
+
+    fn new_with_options(data: String, error_correction: ErrorCorrectionLevel, options: EncodingOptions) -> Result<Self, String> {
+        let (version, segments) = match find_version(&data, &error_correction, &options) {
+            Ok(Some(found)) => found,
+            Ok(None) => { return Err(String::from("Data too long for encoding!")) },
+            Err(err) => { return Err(format!("{:?}", err)) },
+        };
+
+        Ok(Self::from_segments(data, segments, error_correction, version))
+    }
+
+    // encodes all of the data in the single mode that can represent every character
+    pub fn new_single_mode(data: String, error_correction: ErrorCorrectionLevel) -> Result<Self, String> {
         let encoding = find_encoding(&data);
-        let version = get_size(data.len(), &encoding, &error_correction);
+        let segment = Segment::new(encoding, data.clone());
+        let mut version = get_size(segment.char_count(), &segment.encoding, &error_correction);
 
         if version == 0 {
             return Err(String::from("Data too long for encoding!"))
         }
 
+        // the capacity table does not include the ECI header of non ASCII byte data
+        let segments = add_headers(vec![segment], &EncodingOptions::default());
+        while !segments_fit(&segments, version, &error_correction).map_err(|err| format!("{:?}", err))? {
+            if version == 40 {
+                return Err(String::from("Data too long for encoding!"))
+            }
+            version += 1;
+        }
+
+        Ok(Self::from_segments(data, segments, error_correction, version))
+    }
+
+    // splits the data over up to 16 linked symbols, as many as needed if no symbol count is requested
+    // every symbol starts with a header holding its index, the number of symbols and the parity of all data
+    pub fn new_structured_append(data: String, error_correction: ErrorCorrectionLevel, symbols: Option<usize>) -> Result<Vec<Self>, String> {
+        let chars: Vec<char> = data.chars().collect();
+
+        let symbol_counts = match symbols {
+            Some(count) if (1..=MAX_STRUCTURED_APPEND_SYMBOLS).contains(&count) && count <= chars.len().max(1) => count..=count,
+            Some(_) => { return Err(String::from("Invalid number of structured append symbols!")) },
+            None    => 1..=MAX_STRUCTURED_APPEND_SYMBOLS,
+        };
+
+        'counts: for total in symbol_counts {
+            let mut codes = Vec::with_capacity(total);
+            let mut start = 0;
+
+            for index in 0..total {
+                // spread the characters as evenly as possible, the first symbols take one more if it does not divide
+                let len = chars.len() / total + if index < chars.len() % total { 1 } else { 0 };
+                let chunk: String = chars[start..start + len].iter().collect();
+                start += len;
+
+                // the parity depends on the modes chosen for every symbol, it is filled in once all are encoded
+                let header = Segment::new(QREncoding::StructuredAppend { index: index as u8, total: total as u8, parity: 0 }, String::new());
+                let options = EncodingOptions { structured_append: Some(header), ..Default::default() };
+                match find_version(&chunk, &error_correction, &options) {
+                    Ok(Some((version, segments))) => codes.push(Self::from_segments(chunk, segments, error_correction, version)),
+                    Ok(None) => { continue 'counts },
+                    Err(err) => { return Err(format!("{:?}", err)) },
+                }
+            }
+
+            set_structured_append_parity(&mut codes);
+            return Ok(codes);
+        }
+
+        Err(String::from("Data too long for structured append!"))
+    }
+
+    fn from_segments(raw_data: String, segments: Vec<Segment>, error_correction: ErrorCorrectionLevel, version: usize) -> Self {
         let err_metadata = get_err_metadata(version, &error_correction).unwrap();
 
-        Ok(QRCode {
-            raw_data: data,
-            encoding,
+        QRCode {
+            raw_data,
+            segments,
             error_correction,
             version,
             err_metadata,
-        })
+            mask: None,
+        }
+    }
+
+    pub fn raw_data(&self) -> &str {
+        &self.raw_data
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
     }
 
     pub fn encode(&self) -> Result<String, QRCodeError> {
         let mut bit_buffer = String::new();
 
-        let encoding = get_encoding(self);
-        bit_buffer.push_str(encoding);
+        for segment in self.segments.iter() {
+            let encoding = get_encoding(segment);
+            bit_buffer.push_str(encoding);
+
+            let data_len = get_data_len(segment, self.version)?;
+            bit_buffer.push_str(&data_len);
 
-        let data_len = get_data_len(self)?;
-        bit_buffer.push_str(&data_len);
+            encode_data(segment, &mut bit_buffer);
+        }
 
-        encode_data(self, &mut bit_buffer);
         add_padding(self, &mut bit_buffer)?;
-        
-        return Ok(bit_buffer);
+
+        Ok(bit_buffer)
     }
 
-    pub fn get_coeffs(&self) -> Result<[u32; 255], QRCodeError> {
+    pub fn get_data_codewords(&self) -> Result<Vec<u8>, QRCodeError> {
         let bitbuf = self.encode()?;
         assert!(bitbuf.len() % 8 == 0, "Bit buffer has invalid length that is not a multiple of 8.");
         let chunks = bitbuf.len() / 8;
 
-        let mut coeffs = vec![0; chunks];
-        for i in 0..chunks {
+        let mut codewords = vec![0; chunks];
+        for (i, codeword) in codewords.iter_mut().enumerate() {
             let byte = &bitbuf[i*8..(i + 1)*8];
-            let coeff = u8::from_str_radix(byte, 2)
+            *codeword = u8::from_str_radix(byte, 2)
                                 .expect("This should be a valid bin string!");
-            coeffs[i] = coeff;
         }
 
-        Ok(nums_to_coeffs(&coeffs))
+        Ok(codewords)
+    }
+
+    // splits the data codewords into the blocks of group 1 followed by the blocks of group 2
+    pub fn get_data_blocks(&self) -> Result<Vec<Vec<u8>>, QRCodeError> {
+        Ok(split_blocks(&self.get_data_codewords()?, &self.err_metadata))
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> &ErrorCorrectionLevel {
+        &self.error_correction
+    }
+
+    // interleaved data codewords followed by the interleaved error correction codewords and the remainder bits
+    // source: https://www.thonky.com/qr-code-tutorial/structure-final-message
+    pub fn final_message(&self) -> Result<String, QRCodeError> {
+        let data_blocks = self.get_data_blocks()?;
+        let error_blocks = self.gen_error_codewords()?;
+
+        let mut message = interleave_blocks(&data_blocks, &error_blocks);
+        for _ in 0..REMAINDER_BITS[self.version - 1] { message.push('0'); }
+
+        Ok(message)
+    }
+
+    // forces one of the eight mask patterns instead of choosing the one with the lowest penalty
+    pub fn set_mask(&mut self, mask: usize) -> Result<(), QRCodeError> {
+        if mask >= NUM_MASKS {
+            return Err(QRCodeError::InvalidMask);
+        }
+
+        self.mask = Some(mask);
+        Ok(())
+    }
+
+    // finished symbol grid with all function patterns, the masked final message and the format/version information
+    pub fn gen_matrix(&self) -> Result<QRMatrix, QRCodeError> {
+        let mut matrix = QRMatrix::new(self.version);
+        matrix.place_data(&self.final_message()?);
+
+        let mask = match self.mask {
+            Some(mask) => mask,
+            None => (0..NUM_MASKS).min_by_key(|&mask| {
+                let mut candidate = matrix.clone();
+                candidate.apply_mask(mask);
+                candidate.place_format_info(&self.error_correction, mask);
+                candidate.penalty()
+            }).unwrap(),
+        };
+        matrix.apply_mask(mask);
+        matrix.place_format_info(&self.error_correction, mask);
+
+        Ok(matrix)
+    }
+
+    // error correction codewords for each data block, all blocks share the same number of them
+    pub fn gen_error_codewords(&self) -> Result<Vec<Vec<u8>>, QRCodeError> {
+        Ok(gen_block_error_codewords(&self.get_data_blocks()?, self.err_metadata.words_per_block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(matrix: &QRMatrix) -> Vec<String> {
+        (0..matrix.size())
+            .map(|y| (0..matrix.size()).map(|x| if matrix.get(x, y) { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    // the expected symbols were generated with the reference encoder qrcodegen and the same mask
+    fn masked_matrix(data: &str, error_correction: ErrorCorrectionLevel, mask: usize) -> QRMatrix {
+        let mut code = QRCode::new(String::from(data), error_correction).unwrap();
+        code.set_mask(mask).unwrap();
+        code.gen_matrix().unwrap()
+    }
+
+    #[test]
+    fn golden_hello_world_1q_mask_6() {
+        let expected = [
+            "#######....#..#######",
+            "#.....#.##..#.#.....#",
+            "#.###.#..#.##.#.###.#",
+            "#.###.#.#####.#.###.#",
+            "#.###.#.##.#..#.###.#",
+            "#.....#..#..#.#.....#",
+            "#######.#.#.#.#######",
+            "........##.##........",
+            ".#.####.##..###.##.#.",
+            "#.####.#....####.###.",
+            "..#.#.##...#..##.....",
+            "#.##.#...#.##...##...",
+            "##.########.###.#####",
+            "........#...#..#.#...",
+            "#######..##..##..####",
+            "#.....#.#.#..#..#.###",
+            "#.###.#.##.#..#...###",
+            "#.###.#.#.###...#.#..",
+            "#.###.#..#....#....##",
+            "#.....#.###..###..##.",
+            "#######..#.#.......#.",
+        ];
+        assert_eq!(rows(&masked_matrix("HELLO WORLD", ErrorCorrectionLevel::Q, 6)), expected);
+    }
+
+    #[test]
+    fn golden_numeric_1m_mask_2() {
+        let expected = [
+            "#######..#.##.#######",
+            "#.....#..####.#.....#",
+            "#.###.#.#.....#.###.#",
+            "#.###.#.##....#.###.#",
+            "#.###.#.#.###.#.###.#",
+            "#.....#.#...#.#.....#",
+            "#######.#.#.#.#######",
+            "........#..##........",
+            "#.#####..#..#.#####..",
+            "...#.#.##.#.#..#.##..",
+            "..#...##.#.#.#..#####",
+            "....#....#.....####..",
+            "...######..#.#..#....",
+            "........#.#####..##..",
+            "#######..##.#.##.....",
+            "#.....#.#.#####...#.#",
+            "#.###.#.#...#..#.##..",
+            "#.###.#.##..#..#.....",
+            "#.###.#.#.##.#..#.#..",
+            "#.....#........##.##.",
+            "#######.####.#..#.#..",
+        ];
+        assert_eq!(rows(&masked_matrix("01234567", ErrorCorrectionLevel::M, 2)), expected);
+    }
+
+    #[test]
+    fn golden_alphanumeric_3m_mask_0() {
+        let expected = [
+            "#######...#.##..#..##.#######",
+            "#.....#.#.##..####....#.....#",
+            "#.###.#...#.###.###.#.#.###.#",
+            "#.###.#..##..###....#.#.###.#",
+            "#.###.#.#####.#..#.##.#.###.#",
+            "#.....#..#.###...####.#.....#",
+            "#######.#.#.#.#.#.#.#.#######",
+            ".........#..###.#...#........",
+            "#.#.#.#..#..#.##....#...#..#.",
+            ".......#.##.#.##.####.###.###",
+            "....###..#.###...##.#..#..#.#",
+            "####......#.#..#.#....####...",
+            "###.######.#....##.###...####",
+            "...#.#..#.#..#.##.#.#######.#",
+            "##..###.##.##.###.###.##.##.#",
+            "#......#.##.###.###..##..##..",
+            "#.....#..##...##.#.###.###.##",
+            ".........#....##.###...#.#.##",
+            "#.#..###..#.##....####...#...",
+            ".##.##....#....#...#...#.####",
+            "#.###.#.###.#...##..#####...#",
+            "........#.#..#.####.#...###..",
+            "#######..#....#####.#.#.#.##.",
+            "#.....#....####.##.##...#....",
+            "#.###.#.###.#.####..#####.###",
+            "#.###.#...#.####.#.....###...",
+            "#.###.#.##..###.#.##.######.#",
+            "#.....#....##..#.##.#.#....##",
+            "#######.#.###...#....#..###.#",
+        ];
+        assert_eq!(rows(&masked_matrix("THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG", ErrorCorrectionLevel::M, 0)), expected);
+    }
+
+    #[test]
+    fn set_mask_rejects_invalid_mask() {
+        let mut code = QRCode::new(String::from("HELLO WORLD"), ErrorCorrectionLevel::Q).unwrap();
+        assert!(matches!(code.set_mask(NUM_MASKS), Err(QRCodeError::InvalidMask)));
     }
 
-    pub fn gen_error_codewords(&self) -> Vec<u32>{
-        // these coefficients are powers of two
-        let mut message_poly_coeffs = self.get_coeffs().unwrap();
-        get_code_words(&mut message_poly_coeffs[..], self.err_metadata.words_per_block as u32)
+    #[test]
+    fn chosen_mask_has_lowest_penalty() {
+        for data in ["HELLO WORLD", "01234567", "https://example.com/?q=mask", "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG"] {
+            let chosen = QRCode::new(String::from(data), ErrorCorrectionLevel::M).unwrap().gen_matrix().unwrap();
+            let candidates: Vec<QRMatrix> = (0..NUM_MASKS).map(|mask| masked_matrix(data, ErrorCorrectionLevel::M, mask)).collect();
+            let lowest = candidates.iter().min_by_key(|candidate| candidate.penalty()).unwrap();
+
+            assert_eq!(rows(&chosen), rows(lowest));
+        }
+    }
+
+    #[test]
+    fn numeric_groups_keep_leading_zeros() {
+        let mut bits = String::new();
+        for group in ["007", "000", "100", "01", "0"] { encode_numeric(&mut bits, group); }
+        assert_eq!(bits, concat!("0000000111", "0000000000", "0001100100", "0000001", "0000"));
+
+        // mode indicator, 10 bit character count and the groups 001, 234 and 5
+        let code = QRCode::new(String::from("0012345"), ErrorCorrectionLevel::M).unwrap();
+        assert!(code.encode().unwrap().starts_with(concat!("0001", "0000000111", "0000000001", "0011101010", "0101")));
+    }
+
+    #[test]
+    fn mixed_segments_are_shorter_than_single_mode() {
+        let data = "12345678901234a";
+        let total_bits = |code: &QRCode| code.segments().iter().map(|segment| segment.total_bits(code.version()).unwrap()).sum::<usize>();
+
+        let mixed = QRCode::new(String::from(data), ErrorCorrectionLevel::M).unwrap();
+        let single = QRCode::new_single_mode(String::from(data), ErrorCorrectionLevel::M).unwrap();
+
+        let encodings: Vec<&QREncoding> = mixed.segments().iter().map(|segment| segment.encoding()).collect();
+        assert_eq!(encodings, [&QREncoding::Numeric, &QREncoding::Byte]);
+        assert_eq!(total_bits(&mixed), 81);
+        assert_eq!(total_bits(&single), 132);
+    }
+
+    // source: ISO/IEC 18004:2015 section 7.4.6, examples for both Shift JIS ranges
+    #[test]
+    fn kanji_characters_are_packed_into_13_bits() {
+        assert_eq!(get_kanji_sjis('点'), Some(0x935F));
+        assert_eq!(get_kanji_sjis('茗'), Some(0xE4AA));
+        assert_eq!(get_kanji_sjis('A'), None);
+
+        assert_eq!(encode_kanji('点'), format!("{:013b}", 0x0D9F));
+        assert_eq!(encode_kanji('茗'), format!("{:013b}", 0x1AAA));
+    }
+
+    #[test]
+    fn kanji_run_in_mixed_data_uses_kanji_mode() {
+        let code = QRCode::new(String::from("点茗漢字テスト ok"), ErrorCorrectionLevel::M).unwrap();
+
+        let encodings: Vec<&QREncoding> = code.segments().iter().map(|segment| segment.encoding()).collect();
+        assert_eq!(encodings, [&QREncoding::Kanji, &QREncoding::Byte]);
+        assert_eq!(code.segments()[0].data(), "点茗漢字テスト");
+    }
+
+    #[test]
+    fn structured_append_parity_uses_encoded_bytes() {
+        let data = "漢字テスト0123456789点茗ABCDEF";
+        let codes = QRCode::new_structured_append(String::from(data), ErrorCorrectionLevel::M, Some(2)).unwrap();
+        assert_eq!(codes.len(), 2);
+        assert!(codes.iter().all(|code| code.segments().iter().any(|segment| segment.encoding() == &QREncoding::Kanji)));
+
+        let xor = |bytes: &[u8]| bytes.iter().fold(0, |parity, byte| parity ^ byte);
+        let expected = xor(&SHIFT_JIS.encode(data).0);
+        assert_ne!(expected, xor(data.as_bytes()));
+
+        for (index, code) in codes.iter().enumerate() {
+            let header = QREncoding::StructuredAppend { index: index as u8, total: 2, parity: expected };
+            assert_eq!(code.segments()[0].encoding(), &header);
+        }
     }
 }
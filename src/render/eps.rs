@@ -0,0 +1,110 @@
+use crate::matrix::QRMatrix;
+use crate::render::{format_number, print_runs, Cmyk, PrintOptions};
+
+// source: https://www.adobe.com/content/dam/acom/en/devnet/actionscript/articles/5002.EPSF_Spec.pdf
+
+fn set_color(color: &Cmyk) -> String {
+    let (c, m, y, k) = color;
+    format!("{} {} {} {} setcmykcolor\n", format_number(*c), format_number(*m), format_number(*y), format_number(*k))
+}
+
+// the symbol as encapsulated PostScript in CMYK, every run of dark modules is filled as one rectangle
+// the coordinates are in modules and scaled to the module size in millimetres
+pub fn to_eps(matrix: &QRMatrix, options: &PrintOptions) -> String {
+    let quiet_zone = options.quiet_zone.unwrap_or_else(|| matrix.quiet_zone());
+    let (width, height) = (matrix.width() + 2 * quiet_zone, matrix.height() + 2 * quiet_zone);
+    let scale = options.module_size_points();
+    let (width_pt, height_pt) = (width as f64 * scale, height as f64 * scale);
+
+    let mut eps = String::from("%!PS-Adobe-3.0 EPSF-3.0\n");
+    eps.push_str(&format!("%%BoundingBox: 0 0 {} {}\n", width_pt.ceil(), height_pt.ceil()));
+    eps.push_str(&format!("%%HiResBoundingBox: 0 0 {} {}\n", format_number(width_pt), format_number(height_pt)));
+    eps.push_str("%%LanguageLevel: 2\n");
+    eps.push_str("%%EndComments\n");
+
+    eps.push_str("gsave\n");
+    eps.push_str(&format!("{} {} scale\n", format_number(scale), format_number(scale)));
+
+    if let Some(background) = &options.background {
+        eps.push_str(&set_color(background));
+        eps.push_str(&format!("0 0 {} {} rectfill\n", width, height));
+    }
+
+    // x y width r fills a run of dark modules one module high
+    eps.push_str("/r { 1 rectfill } bind def\n");
+    eps.push_str(&set_color(&options.foreground));
+    for (x, y, len) in print_runs(matrix, quiet_zone) {
+        eps.push_str(&format!("{} {} {} r\n", x, y, len));
+    }
+
+    eps.push_str("grestore\n");
+    eps.push_str("%%EOF\n");
+    eps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the M1 symbol of "1", 11 x 11 modules
+    // #######.#.#
+    // #.....#.#..
+    // #.###.#..##
+    // #.###.#.#..
+    // #.###.#..##
+    // #.....#....
+    // #######..##
+    // ........#.#
+    // ##...#...##
+    // .#.######.#
+    // ########.##
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    // 1 mm modules are 72 / 25.4 = 2.8346 points, the symbol 31.1811 points wide
+    // the runs start at the bottom row, so the top row of the symbol has y = 10
+    #[test]
+    fn m1_symbol_with_millimetre_modules() {
+        let options = PrintOptions { module_size: 1.0, quiet_zone: Some(0), background: Some((0.0, 0.0, 0.0, 0.0)), ..Default::default() };
+        let expected = concat!(
+            "%!PS-Adobe-3.0 EPSF-3.0\n",
+            "%%BoundingBox: 0 0 32 32\n",
+            "%%HiResBoundingBox: 0 0 31.1811 31.1811\n",
+            "%%LanguageLevel: 2\n",
+            "%%EndComments\n",
+            "gsave\n",
+            "2.8346 2.8346 scale\n",
+            "0 0 0 0 setcmykcolor\n",
+            "0 0 11 11 rectfill\n",
+            "/r { 1 rectfill } bind def\n",
+            "0 0 0 1 setcmykcolor\n",
+            "0 10 7 r\n8 10 1 r\n10 10 1 r\n",
+            "0 9 1 r\n6 9 1 r\n8 9 1 r\n",
+            "0 8 1 r\n2 8 3 r\n6 8 1 r\n9 8 2 r\n",
+            "0 7 1 r\n2 7 3 r\n6 7 1 r\n8 7 1 r\n",
+            "0 6 1 r\n2 6 3 r\n6 6 1 r\n9 6 2 r\n",
+            "0 5 1 r\n6 5 1 r\n",
+            "0 4 7 r\n9 4 2 r\n",
+            "8 3 1 r\n10 3 1 r\n",
+            "0 2 2 r\n5 2 1 r\n9 2 2 r\n",
+            "1 1 1 r\n3 1 6 r\n10 1 1 r\n",
+            "0 0 8 r\n9 0 2 r\n",
+            "grestore\n",
+            "%%EOF\n",
+        );
+
+        assert_eq!(to_eps(&symbol(), &options), expected);
+    }
+
+    // the default 0.5 mm modules with the quiet zone of 2 modules give 7.5 mm or 21.2598 points
+    #[test]
+    fn default_options_add_the_quiet_zone() {
+        let eps = to_eps(&symbol(), &PrintOptions::default());
+
+        assert!(eps.contains("%%BoundingBox: 0 0 22 22\n%%HiResBoundingBox: 0 0 21.2598 21.2598\n"));
+        assert!(eps.contains("1.4173 1.4173 scale\n/r { 1 rectfill } bind def\n0 0 0 1 setcmykcolor\n2 12 7 r\n"));
+    }
+}
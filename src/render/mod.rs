@@ -0,0 +1,143 @@
+pub mod svg;
+pub mod png;
+pub mod terminal;
+pub mod netpbm;
+pub mod xbm;
+pub mod eps;
+pub mod pdf;
+pub mod style;
+
+use crate::matrix::QRMatrix;
+
+// runs of dark modules in row y as start column and length, renderers draw each run as a single rectangle
+pub(crate) fn dark_runs(matrix: &QRMatrix, y: usize) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut x = 0;
+
+    while x < matrix.width() {
+        if !matrix.get(x, y) {
+            x += 1;
+            continue;
+        }
+
+        let start = x;
+        while x < matrix.width() && matrix.get(x, y) { x += 1; }
+        runs.push((start, x - start));
+    }
+
+    runs
+}
+
+// the module at (x, y) of the symbol surrounded by quiet_zone light modules on every side
+pub(crate) fn get_with_quiet_zone(matrix: &QRMatrix, quiet_zone: usize, x: usize, y: usize) -> bool {
+    match (x.checked_sub(quiet_zone), y.checked_sub(quiet_zone)) {
+        (Some(x), Some(y)) if x < matrix.width() && y < matrix.height() => matrix.get(x, y),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RasterOptions {
+    // edge length of a module in pixels
+    pub scale: usize,
+    // width of the light border in modules, the minimum of the symbol type if None
+    pub quiet_zone: Option<usize>,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self { scale: 4, quiet_zone: None }
+    }
+}
+
+// pixel view of a symbol with its quiet zone, every module is scale x scale pixels
+pub(crate) struct Raster<'a> {
+    matrix: &'a QRMatrix,
+    quiet_zone: usize,
+    scale: usize,
+}
+
+impl<'a> Raster<'a> {
+    pub(crate) fn new(matrix: &'a QRMatrix, options: &RasterOptions) -> Self {
+        let quiet_zone = options.quiet_zone.unwrap_or_else(|| matrix.quiet_zone());
+        Self { matrix, quiet_zone, scale: options.scale.max(1) }
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        (self.matrix.width() + 2 * self.quiet_zone) * self.scale
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        (self.matrix.height() + 2 * self.quiet_zone) * self.scale
+    }
+
+    pub(crate) fn is_dark(&self, px: usize, py: usize) -> bool {
+        get_with_quiet_zone(self.matrix, self.quiet_zone, px / self.scale, py / self.scale)
+    }
+
+    // pixel row py with 8 pixels per byte, the first pixel in the most significant bit and 1 for dark
+    // the last byte is padded with light pixels
+    pub(crate) fn packed_row(&self, py: usize) -> Vec<u8> {
+        let mut row = vec![0u8; self.width().div_ceil(8)];
+        for px in 0..self.width() {
+            if self.is_dark(px, py) { row[px / 8] |= 0x80 >> (px % 8); }
+        }
+
+        row
+    }
+}
+
+// points per millimetre, PostScript and PDF measure in 1/72 inch
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+// cyan, magenta, yellow and black between 0.0 and 1.0
+pub type Cmyk = (f64, f64, f64, f64);
+
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    // edge length of a module in millimetres
+    pub module_size: f64,
+    // width of the light border in modules, the minimum of the symbol type if None
+    pub quiet_zone: Option<usize>,
+    pub foreground: Cmyk,
+    // the background is left unprinted if None
+    pub background: Option<Cmyk>,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            module_size: 0.5,
+            quiet_zone: None,
+            foreground: (0.0, 0.0, 0.0, 1.0),
+            background: None,
+        }
+    }
+}
+
+impl PrintOptions {
+    pub(crate) fn module_size_points(&self) -> f64 {
+        self.module_size * POINTS_PER_MM
+    }
+}
+
+// number for PostScript and PDF with at most 4 decimals and no trailing zeros
+pub(crate) fn format_number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+
+    if text == "-0" { String::from("0") } else { text.to_string() }
+}
+
+// the dark runs of every row in module coordinates with the origin in the bottom left corner, as x, y and length
+// both PostScript and PDF count y upwards, so the first row of the symbol is the topmost
+pub(crate) fn print_runs(matrix: &QRMatrix, quiet_zone: usize) -> Vec<(usize, usize, usize)> {
+    let mut runs = Vec::new();
+
+    for y in 0..matrix.height() {
+        let flipped_y = matrix.height() - 1 - y + quiet_zone;
+        runs.extend(dark_runs(matrix, y).into_iter().map(|(x, len)| (x + quiet_zone, flipped_y, len)));
+    }
+
+    runs
+}
@@ -0,0 +1,205 @@
+use crate::matrix::QRMatrix;
+use crate::render::{Raster, RasterOptions};
+
+// source: https://netpbm.sourceforge.net/doc/pbm.html and https://netpbm.sourceforge.net/doc/pgm.html
+
+// lines of the plain formats should not be longer than 70 characters
+const MAX_PLAIN_LINE_LEN: usize = 70;
+
+// appends the values of one pixel row separated by spaces, wrapping lines before they get too long
+fn push_plain_row(text: &mut String, values: impl Iterator<Item = &'static str>) {
+    let mut line_len = 0;
+
+    for value in values {
+        if line_len > 0 && line_len + 1 + value.len() > MAX_PLAIN_LINE_LEN {
+            text.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            text.push(' ');
+            line_len += 1;
+        }
+
+        text.push_str(value);
+        line_len += value.len();
+    }
+
+    text.push('\n');
+}
+
+// plain PBM (P1), 1 is a dark and 0 a light pixel
+pub fn to_pbm_plain(matrix: &QRMatrix, options: &RasterOptions) -> String {
+    let raster = Raster::new(matrix, options);
+    let mut pbm = format!("P1\n{} {}\n", raster.width(), raster.height());
+
+    for py in 0..raster.height() {
+        push_plain_row(&mut pbm, (0..raster.width()).map(|px| if raster.is_dark(px, py) { "1" } else { "0" }));
+    }
+
+    pbm
+}
+
+// raw PBM (P4), 8 pixels per byte with the first pixel in the most significant bit, every row starts at a new byte
+pub fn to_pbm(matrix: &QRMatrix, options: &RasterOptions) -> Vec<u8> {
+    let raster = Raster::new(matrix, options);
+    let mut pbm = format!("P4\n{} {}\n", raster.width(), raster.height()).into_bytes();
+
+    for py in 0..raster.height() {
+        pbm.extend(raster.packed_row(py));
+    }
+
+    pbm
+}
+
+// plain PGM (P2) with a maximum value of 255, 0 is a dark and 255 a light pixel
+pub fn to_pgm_plain(matrix: &QRMatrix, options: &RasterOptions) -> String {
+    let raster = Raster::new(matrix, options);
+    let mut pgm = format!("P2\n{} {}\n255\n", raster.width(), raster.height());
+
+    for py in 0..raster.height() {
+        push_plain_row(&mut pgm, (0..raster.width()).map(|px| if raster.is_dark(px, py) { "0" } else { "255" }));
+    }
+
+    pgm
+}
+
+// raw PGM (P5) with one byte per pixel
+pub fn to_pgm(matrix: &QRMatrix, options: &RasterOptions) -> Vec<u8> {
+    let raster = Raster::new(matrix, options);
+    let mut pgm = format!("P5\n{} {}\n255\n", raster.width(), raster.height()).into_bytes();
+
+    for py in 0..raster.height() {
+        pgm.extend((0..raster.width()).map(|px| if raster.is_dark(px, py) { 0u8 } else { 255u8 }));
+    }
+
+    pgm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the M1 symbol of "1", 11 x 11 modules
+    // #######.#.#
+    // #.....#.#..
+    // #.###.#..##
+    // #.###.#.#..
+    // #.###.#..##
+    // #.....#....
+    // #######..##
+    // ........#.#
+    // ##...#...##
+    // .#.######.#
+    // ########.##
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    fn options(scale: usize, quiet_zone: usize) -> RasterOptions {
+        RasterOptions { scale, quiet_zone: Some(quiet_zone) }
+    }
+
+    // every row of the image repeated scale times
+    fn scaled_rows(rows: &[&[u8]], scale: usize) -> Vec<u8> {
+        rows.iter().flat_map(|row| std::iter::repeat_n(*row, scale).flatten().copied()).collect()
+    }
+
+    #[test]
+    fn plain_pbm() {
+        let expected = "P1\n11 11\n\
+            1 1 1 1 1 1 1 0 1 0 1\n\
+            1 0 0 0 0 0 1 0 1 0 0\n\
+            1 0 1 1 1 0 1 0 0 1 1\n\
+            1 0 1 1 1 0 1 0 1 0 0\n\
+            1 0 1 1 1 0 1 0 0 1 1\n\
+            1 0 0 0 0 0 1 0 0 0 0\n\
+            1 1 1 1 1 1 1 0 0 1 1\n\
+            0 0 0 0 0 0 0 0 1 0 1\n\
+            1 1 0 0 0 1 0 0 0 1 1\n\
+            0 1 0 1 1 1 1 1 1 0 1\n\
+            1 1 1 1 1 1 1 1 0 1 1\n";
+        assert_eq!(to_pbm_plain(&symbol(), &options(1, 0)), expected);
+    }
+
+    #[test]
+    fn raw_pbm() {
+        let mut expected = b"P4\n11 11\n".to_vec();
+        expected.extend([
+            0xfe, 0xa0, 0x82, 0x80, 0xba, 0x60, 0xba, 0x80, 0xba, 0x60, 0x82, 0x00,
+            0xfe, 0x60, 0x00, 0xa0, 0xc4, 0x60, 0x5f, 0xa0, 0xff, 0x60,
+        ]);
+        assert_eq!(to_pbm(&symbol(), &options(1, 0)), expected);
+    }
+
+    // 26 pixels per row are padded to 4 bytes
+    #[test]
+    fn raw_pbm_scaled_with_quiet_zone() {
+        let rows: [&[u8]; 13] = [
+            &[0x00, 0x00, 0x00, 0x00],
+            &[0x3f, 0xff, 0x33, 0x00],
+            &[0x30, 0x03, 0x30, 0x00],
+            &[0x33, 0xf3, 0x0f, 0x00],
+            &[0x33, 0xf3, 0x30, 0x00],
+            &[0x33, 0xf3, 0x0f, 0x00],
+            &[0x30, 0x03, 0x00, 0x00],
+            &[0x3f, 0xff, 0x0f, 0x00],
+            &[0x00, 0x00, 0x33, 0x00],
+            &[0x3c, 0x0c, 0x0f, 0x00],
+            &[0x0c, 0xff, 0xf3, 0x00],
+            &[0x3f, 0xff, 0xcf, 0x00],
+            &[0x00, 0x00, 0x00, 0x00],
+        ];
+        let mut expected = b"P4\n26 26\n".to_vec();
+        expected.extend(scaled_rows(&rows, 2));
+        assert_eq!(to_pbm(&symbol(), &options(2, 1)), expected);
+    }
+
+    #[test]
+    fn plain_pgm_with_quiet_zone() {
+        let expected = "P2\n13 13\n255\n\
+            255 255 255 255 255 255 255 255 255 255 255 255 255\n\
+            255 0 0 0 0 0 0 0 255 0 255 0 255\n\
+            255 0 255 255 255 255 255 0 255 0 255 255 255\n\
+            255 0 255 0 0 0 255 0 255 255 0 0 255\n\
+            255 0 255 0 0 0 255 0 255 0 255 255 255\n\
+            255 0 255 0 0 0 255 0 255 255 0 0 255\n\
+            255 0 255 255 255 255 255 0 255 255 255 255 255\n\
+            255 0 0 0 0 0 0 0 255 255 0 0 255\n\
+            255 255 255 255 255 255 255 255 255 0 255 0 255\n\
+            255 0 0 255 255 255 0 255 255 255 0 0 255\n\
+            255 255 0 255 0 0 0 0 0 0 255 0 255\n\
+            255 0 0 0 0 0 0 0 0 255 0 0 255\n\
+            255 255 255 255 255 255 255 255 255 255 255 255 255\n";
+        assert_eq!(to_pgm_plain(&symbol(), &options(1, 1)), expected);
+    }
+
+    #[test]
+    fn raw_pgm_scaled() {
+        let rows: Vec<Vec<u8>> = [
+            "##############..##..##",
+            "##..........##..##....",
+            "##..######..##....####",
+            "##..######..##..##....",
+            "##..######..##....####",
+            "##..........##........",
+            "##############....####",
+            "................##..##",
+            "####......##......####",
+            "..##..############..##",
+            "################..####",
+        ].iter().map(|row| row.bytes().map(|pixel| if pixel == b'#' { 0 } else { 255 }).collect()).collect();
+        let rows: Vec<&[u8]> = rows.iter().map(|row| row.as_slice()).collect();
+
+        let mut expected = b"P5\n22 22\n255\n".to_vec();
+        expected.extend(scaled_rows(&rows, 2));
+        assert_eq!(to_pgm(&symbol(), &options(2, 0)), expected);
+    }
+
+    #[test]
+    fn plain_rows_wrap_before_70_characters() {
+        let mut text = String::new();
+        push_plain_row(&mut text, std::iter::repeat_n("1", 36));
+        assert_eq!(text, format!("{}\n1\n", vec!["1"; 35].join(" ")));
+    }
+}
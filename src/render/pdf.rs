@@ -0,0 +1,151 @@
+use crate::matrix::QRMatrix;
+use crate::render::{format_number, print_runs, Cmyk, PrintOptions};
+
+// source: https://opensource.adobe.com/dc-acrobat-sdk-docs/pdfstandards/PDF32000_2008.pdf
+
+// operands of the k (fill color) operator
+fn fill_color(color: &Cmyk) -> String {
+    let (c, m, y, k) = color;
+    format!("{} {} {} {} k\n", format_number(*c), format_number(*m), format_number(*y), format_number(*k))
+}
+
+// content stream drawing all dark runs as rectangles of a single path that is filled once
+fn content_stream(matrix: &QRMatrix, options: &PrintOptions, quiet_zone: usize) -> String {
+    let (width, height) = (matrix.width() + 2 * quiet_zone, matrix.height() + 2 * quiet_zone);
+    let scale = format_number(options.module_size_points());
+
+    let mut content = format!("q\n{} 0 0 {} 0 0 cm\n", scale, scale);
+
+    if let Some(background) = &options.background {
+        content.push_str(&fill_color(background));
+        content.push_str(&format!("0 0 {} {} re f\n", width, height));
+    }
+
+    content.push_str(&fill_color(&options.foreground));
+    for (x, y, len) in print_runs(matrix, quiet_zone) {
+        content.push_str(&format!("{} {} {} 1 re\n", x, y, len));
+    }
+    content.push_str("f\nQ\n");
+
+    content
+}
+
+// single page PDF with the page sized to the symbol and its quiet zone
+pub fn to_pdf(matrix: &QRMatrix, options: &PrintOptions) -> Vec<u8> {
+    let quiet_zone = options.quiet_zone.unwrap_or_else(|| matrix.quiet_zone());
+    let scale = options.module_size_points();
+    let width_pt = (matrix.width() + 2 * quiet_zone) as f64 * scale;
+    let height_pt = (matrix.height() + 2 * quiet_zone) as f64 * scale;
+
+    let content = content_stream(matrix, options, quiet_zone);
+    let objects = [
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        String::from("<< /Type /Pages /Kids [3 0 R] /Count 1 >>"),
+        format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << >> /Contents 4 0 R >>",
+            format_number(width_pt), format_number(height_pt)),
+        format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content),
+    ];
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    // every cross reference entry is exactly 20 bytes long
+    let xref_offset = pdf.len();
+    pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_offset));
+
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the M1 symbol of "1", 11 x 11 modules
+    // #######.#.#
+    // #.....#.#..
+    // #.###.#..##
+    // #.###.#.#..
+    // #.###.#..##
+    // #.....#....
+    // #######..##
+    // ........#.#
+    // ##...#...##
+    // .#.######.#
+    // ########.##
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    fn options() -> PrintOptions {
+        PrintOptions { module_size: 1.0, quiet_zone: Some(0), background: Some((0.0, 0.0, 0.0, 0.0)), ..Default::default() }
+    }
+
+    // 1 mm modules are 72 / 25.4 = 2.8346 points, the runs start at the bottom row
+    #[test]
+    fn content_stream_fills_all_runs_at_once() {
+        let expected = concat!(
+            "q\n",
+            "2.8346 0 0 2.8346 0 0 cm\n",
+            "0 0 0 0 k\n",
+            "0 0 11 11 re f\n",
+            "0 0 0 1 k\n",
+            "0 10 7 1 re\n8 10 1 1 re\n10 10 1 1 re\n",
+            "0 9 1 1 re\n6 9 1 1 re\n8 9 1 1 re\n",
+            "0 8 1 1 re\n2 8 3 1 re\n6 8 1 1 re\n9 8 2 1 re\n",
+            "0 7 1 1 re\n2 7 3 1 re\n6 7 1 1 re\n8 7 1 1 re\n",
+            "0 6 1 1 re\n2 6 3 1 re\n6 6 1 1 re\n9 6 2 1 re\n",
+            "0 5 1 1 re\n6 5 1 1 re\n",
+            "0 4 7 1 re\n9 4 2 1 re\n",
+            "8 3 1 1 re\n10 3 1 1 re\n",
+            "0 2 2 1 re\n5 2 1 1 re\n9 2 2 1 re\n",
+            "1 1 1 1 re\n3 1 6 1 re\n10 1 1 1 re\n",
+            "0 0 8 1 re\n9 0 2 1 re\n",
+            "f\n",
+            "Q\n",
+        );
+
+        assert_eq!(content_stream(&symbol(), &options(), 0), expected);
+    }
+
+    #[test]
+    fn page_is_sized_to_the_symbol() {
+        let pdf = String::from_utf8(to_pdf(&symbol(), &options())).unwrap();
+        let content = content_stream(&symbol(), &options(), 0);
+
+        assert!(pdf.starts_with("%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n"));
+        assert!(pdf.contains("/MediaBox [0 0 31.1811 31.1811]"));
+        assert!(pdf.contains(&format!("4 0 obj\n<< /Length {} >>\nstream\n{}endstream\nendobj\n", content.len(), content)));
+
+        let default = String::from_utf8(to_pdf(&symbol(), &PrintOptions::default())).unwrap();
+        assert!(default.contains("/MediaBox [0 0 21.2598 21.2598]"));
+    }
+
+    // the cross reference table points at the start of every object and startxref at the table
+    #[test]
+    fn cross_references_point_at_the_objects() {
+        let pdf = String::from_utf8(to_pdf(&symbol(), &options())).unwrap();
+
+        let xref_offset = pdf.find("xref\n").unwrap();
+        let startxref = pdf.trim_end_matches("\n%%EOF\n").rsplit('\n').next().unwrap();
+        assert_eq!(startxref.parse::<usize>().unwrap(), xref_offset);
+
+        let entries: Vec<&str> = pdf[xref_offset..].lines().skip(2).take(5).collect();
+        assert_eq!(entries[0], "0000000000 65535 f ");
+        for (i, entry) in entries[1..].iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+            assert_eq!(&entry[10..], " 00000 n ");
+        }
+        assert!(pdf.ends_with(&format!("trailer\n<< /Size 5 /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", xref_offset)));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::matrix::QRMatrix;
+use crate::render::{Raster, RasterOptions};
+
+// source: https://www.w3.org/TR/png/
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+// a stored deflate block holds at most 65535 bytes
+const MAX_STORED_BLOCK_LEN: usize = 0xFFFF;
+
+// CRC-32 of the chunk type and data, reflected polynomial 0xEDB88320
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+// Adler-32 checksum of the uncompressed data at the end of the zlib stream
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in bytes {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+
+    (b << 16) | a
+}
+
+// zlib stream of uncompressed deflate blocks, the 1 bit scanlines of a symbol are small enough to not need compression
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate with a 32K window and no preset dictionary, the check bits make the header a multiple of 31
+    let mut stream = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK_LEN).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_last = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        stream.push(if is_last { 0x01 } else { 0x00 });
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+// the symbol as 1 bit grayscale PNG, every module is scale x scale pixels and dark modules are black
+pub fn to_png(matrix: &QRMatrix, options: &RasterOptions) -> Vec<u8> {
+    let raster = Raster::new(matrix, options);
+    let (width, height) = (raster.width(), raster.height());
+
+    // every scanline starts with filter type 0 followed by 8 pixels per byte, 1 is white
+    let mut scanlines = Vec::with_capacity((width.div_ceil(8) + 1) * height);
+    for py in 0..height {
+        scanlines.push(0);
+        scanlines.extend(raster.packed_row(py).iter().map(|byte| !byte));
+    }
+
+    // width, height, bit depth 1, color type 0 (grayscale), deflate compression, adaptive filtering, no interlace
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the M1 symbol of "1", 11 x 11 modules
+    // #######.#.#
+    // #.....#.#..
+    // #.###.#..##
+    // #.###.#.#..
+    // #.###.#..##
+    // #.....#....
+    // #######..##
+    // ........#.#
+    // ##...#...##
+    // .#.######.#
+    // ########.##
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    // check values of the CRC-32 and Adler-32 definitions, and the CRC that closes every IEND chunk
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    // the expected bytes were assembled separately with the checksums of the zlib library, which also inflates them
+    #[test]
+    fn m1_symbol_without_quiet_zone() {
+        let png = to_png(&symbol(), &RasterOptions { scale: 1, quiet_zone: Some(0) });
+
+        let signature = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        let ihdr = [
+            0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D', b'R',
+            0x00, 0x00, 0x00, 0x0B, 0x00, 0x00, 0x00, 0x0B, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x81, 0xD7, 0x4A, 0x8B,
+        ];
+        // zlib header, a single final stored block of 33 bytes, 11 scanlines of filter type 0 and 2 bytes, Adler-32
+        let idat = [
+            0x00, 0x00, 0x00, 0x2C, b'I', b'D', b'A', b'T',
+            0x78, 0x01, 0x01, 0x21, 0x00, 0xDE, 0xFF,
+            0x00, 0x01, 0x5F, 0x00, 0x7D, 0x7F, 0x00, 0x45, 0x9F, 0x00, 0x45, 0x7F, 0x00, 0x45, 0x9F, 0x00,
+            0x7D, 0xFF, 0x00, 0x01, 0x9F, 0x00, 0xFF, 0x5F, 0x00, 0x3B, 0x9F, 0x00, 0xA0, 0x5F, 0x00, 0x00,
+            0x9F, 0x9B, 0x1B, 0x09, 0xDB,
+            0xC9, 0x9F, 0x7F, 0x25,
+        ];
+        let iend = [0x00, 0x00, 0x00, 0x00, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82];
+
+        assert_eq!(png, [&signature[..], &ihdr, &idat, &iend].concat());
+    }
+
+    #[test]
+    fn scale_and_quiet_zone_set_the_size() {
+        let png = to_png(&symbol(), &RasterOptions { scale: 3, quiet_zone: None });
+
+        // micro symbols need a quiet zone of 2 modules, (11 + 4) * 3 pixels
+        assert_eq!(&png[16..24], &[0x00, 0x00, 0x00, 0x2D, 0x00, 0x00, 0x00, 0x2D]);
+    }
+
+    #[test]
+    fn stored_blocks_are_split_above_65535_bytes() {
+        let data = vec![0xAB; MAX_STORED_BLOCK_LEN + 10];
+        let stream = zlib_stored(&data);
+
+        assert_eq!(stream.len(), 2 + 5 + MAX_STORED_BLOCK_LEN + 5 + 10 + 4);
+        assert_eq!(&stream[..7], &[0x78, 0x01, 0x00, 0xFF, 0xFF, 0x00, 0x00]);
+
+        let second = 7 + MAX_STORED_BLOCK_LEN;
+        assert_eq!(&stream[second..second + 5], &[0x01, 0x0A, 0x00, 0xF5, 0xFF]);
+        assert!(stream[7..second].iter().chain(&stream[second + 5..second + 15]).all(|&byte| byte == 0xAB));
+        assert_eq!(&stream[second + 15..], &[0xC8, 0x7C, 0x10, 0x09]);
+    }
+
+    #[test]
+    fn empty_data_is_a_single_empty_block() {
+        assert_eq!(zlib_stored(&[]), [0x78, 0x01, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x01]);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::matrix::QRMatrix;
+use crate::render::format_number;
+use crate::render::svg::{escape_attribute, open_document, push_background};
+
+// styled SVG output for branded symbols
+// the finder patterns are never drawn with the module shape, they keep their exact square geometry so the 1:1:3:1:1
+// ratio readers look for holds along every line through the center, only their fill can be styled
+
+// corner radius of the rounded module shape, in modules
+const ROUNDED_MODULE_RADIUS: f64 = 0.3;
+
+// source: https://www.w3.org/TR/WCAG21/#contrast-minimum
+// below this contrast ratio between a foreground colour and the background a warning is reported
+// the 3:1 of WCAG 2.1 for graphical objects (1.4.11) assumes a person looking at a screen, a symbol is read by a camera
+// that loses contrast to glare, blur and print gain, so the 4.5:1 minimum for normal text (1.4.3) is used instead
+pub const MIN_CONTRAST_RATIO: f64 = 4.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ModuleShape {
+    #[default]
+    Square,
+    // dots with the diameter of a module
+    Circle,
+    // every module rounded on its own
+    Rounded,
+    // only the corners without dark neighbours are rounded, so runs of modules merge into one shape
+    ConnectedRounded,
+}
+
+#[derive(Debug, Clone)]
+pub enum Fill {
+    Solid(String),
+    // angle in degrees, 0 runs from left to right and 90 from top to bottom
+    LinearGradient { start: String, end: String, angle: f64 },
+    // from the center of the symbol to its corners
+    RadialGradient { center: String, edge: String },
+}
+
+impl Fill {
+    fn colors(&self) -> Vec<&str> {
+        match self {
+            Fill::Solid(color)                          => vec![color],
+            Fill::LinearGradient { start, end, .. }     => vec![start, end],
+            Fill::RadialGradient { center, edge }       => vec![center, edge],
+        }
+    }
+
+    // value of the fill attribute, gradients refer to their definition by id
+    fn paint(&self, id: &str) -> String {
+        match self {
+            Fill::Solid(color) => escape_attribute(color),
+            _                  => format!("url(#{})", id),
+        }
+    }
+
+    // gradient element spanning the symbol without its quiet zone, (x, y) is its top left corner
+    fn definition(&self, id: &str, x: f64, y: f64, width: f64, height: f64) -> Option<String> {
+        let (center_x, center_y) = (x + width / 2.0, y + height / 2.0);
+        let stop = |offset: u8, color: &str| format!("<stop offset=\"{}\" stop-color=\"{}\"/>", offset, escape_attribute(color));
+
+        match self {
+            Fill::Solid(_) => None,
+            Fill::LinearGradient { start, end, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let half = (width * cos.abs() + height * sin.abs()) / 2.0;
+                Some(format!(
+                    "<linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}{}</linearGradient>\n",
+                    id,
+                    format_number(center_x - cos * half), format_number(center_y - sin * half),
+                    format_number(center_x + cos * half), format_number(center_y + sin * half),
+                    stop(0, start), stop(1, end),
+                ))
+            },
+            Fill::RadialGradient { center, edge } => {
+                let radius = (width * width + height * height).sqrt() / 2.0;
+                Some(format!(
+                    "<radialGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" cx=\"{}\" cy=\"{}\" r=\"{}\">{}{}</radialGradient>\n",
+                    id, format_number(center_x), format_number(center_y), format_number(radius),
+                    stop(0, center), stop(1, edge),
+                ))
+            },
+        }
+    }
+}
+
+// rounded or circular finder patterns change their profile off the center lines and make some readers miss them,
+// so only the fill of the finder patterns can differ from the dark modules
+#[derive(Debug, Clone, Default)]
+pub struct EyeStyle {
+    // the fill of the dark modules is used if None
+    pub fill: Option<Fill>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StyledSvgOptions {
+    // edge length of a module in user units, ignored by the responsive mode
+    pub module_size: usize,
+    // width of the light border in modules, the minimum of the symbol type if None
+    pub quiet_zone: Option<usize>,
+    pub foreground: Fill,
+    // no background rectangle is drawn if None, the contrast is then checked against white
+    pub background: Option<String>,
+    // only sets the viewBox so the image scales to its container
+    pub responsive: bool,
+    pub module_shape: ModuleShape,
+    pub eyes: EyeStyle,
+}
+
+impl Default for StyledSvgOptions {
+    fn default() -> Self {
+        Self {
+            module_size: 8,
+            quiet_zone: None,
+            foreground: Fill::Solid(String::from("#000000")),
+            background: Some(String::from("#ffffff")),
+            responsive: false,
+            module_shape: ModuleShape::default(),
+            eyes: EyeStyle::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleWarning {
+    // contrast ratio of a foreground colour against the background below MIN_CONTRAST_RATIO
+    LowContrast { color: String, ratio: f64 },
+    // foreground colour lighter than the background, many readers only find dark symbols on a light background
+    Inverted(String),
+    // colour that is neither #rgb, #rrggbb, black nor white and could not be checked
+    UnknownColor(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct StyledSvg {
+    pub svg: String,
+    // empty if every foreground colour stands out clearly from the background
+    pub warnings: Vec<StyleWarning>,
+}
+
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    let color = color.trim().to_ascii_lowercase();
+    match color.as_str() {
+        "black" => return Some((0, 0, 0)),
+        "white" => return Some((255, 255, 255)),
+        _ => {},
+    }
+
+    let hex = color.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) { return None; }
+
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        3 => Some((channel(&hex[0..1])? * 17, channel(&hex[1..2])? * 17, channel(&hex[2..3])? * 17)),
+        6 => Some((channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+        _ => None,
+    }
+}
+
+// source: https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+fn relative_luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    let linear = |channel: u8| {
+        let c = channel as f64 / 255.0;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+// checks every colour of the modules and the eyes against the background
+pub fn check_contrast(options: &StyledSvgOptions) -> Vec<StyleWarning> {
+    let mut warnings = Vec::new();
+
+    let background = options.background.as_deref().unwrap_or("#ffffff");
+    let background_luminance = match parse_color(background) {
+        Some(rgb) => relative_luminance(rgb),
+        None => return vec![StyleWarning::UnknownColor(background.to_string())],
+    };
+
+    let mut colors = options.foreground.colors();
+    if let Some(fill) = &options.eyes.fill { colors.extend(fill.colors()); }
+    colors.sort_unstable();
+    colors.dedup();
+
+    for color in colors {
+        let luminance = match parse_color(color) {
+            Some(rgb) => relative_luminance(rgb),
+            None => {
+                warnings.push(StyleWarning::UnknownColor(color.to_string()));
+                continue;
+            },
+        };
+
+        let ratio = (luminance.max(background_luminance) + 0.05) / (luminance.min(background_luminance) + 0.05);
+        if ratio < MIN_CONTRAST_RATIO {
+            warnings.push(StyleWarning::LowContrast { color: color.to_string(), ratio });
+        } else if luminance > background_luminance {
+            warnings.push(StyleWarning::Inverted(color.to_string()));
+        }
+    }
+
+    warnings
+}
+
+fn arc(radius: f64, dx: f64, dy: f64) -> String {
+    if radius <= 0.0 { return String::new(); }
+    format!("a{r},{r} 0 0 1 {},{}", format_number(dx), format_number(dy), r = format_number(radius))
+}
+
+// relative line along one axis, left out if it has no length
+fn line(command: char, length: f64) -> String {
+    if length == 0.0 { return String::new(); }
+    format!("{}{}", command, format_number(length))
+}
+
+// closed clockwise outline of a rectangle with the corner radii top left, top right, bottom right and bottom left
+fn rounded_rect(x: f64, y: f64, width: f64, height: f64, [tl, tr, br, bl]: [f64; 4]) -> String {
+    format!(
+        "M{},{}{}{}{}{}{}{}{}{}z",
+        format_number(x + tl), format_number(y),
+        line('h', width - tl - tr), arc(tr, tr, tr),
+        line('v', height - tr - br), arc(br, -br, br),
+        line('h', -(width - br - bl)), arc(bl, -bl, -bl),
+        line('v', -(height - bl - tl)), arc(tl, tl, -tl),
+    )
+}
+
+// the styled symbol as SVG document, the dark modules and the finder patterns are drawn by one path each
+pub fn to_styled_svg(matrix: &QRMatrix, options: &StyledSvgOptions) -> StyledSvg {
+    let quiet_zone = options.quiet_zone.unwrap_or_else(|| matrix.quiet_zone());
+    let width = matrix.width() + 2 * quiet_zone;
+    let height = matrix.height() + 2 * quiet_zone;
+
+    let finders = matrix.finder_patterns();
+    let in_finder = |x: usize, y: usize| finders.iter().any(|&(fx, fy, size)| {
+        (fx..fx + size).contains(&x) && (fy..fy + size).contains(&y)
+    });
+    // dark modules outside of the finder patterns, neighbours outside of the symbol are light
+    let is_dark = |x: isize, y: isize| {
+        x >= 0 && y >= 0 && (x as usize) < matrix.width() && (y as usize) < matrix.height()
+            && matrix.get(x as usize, y as usize) && !in_finder(x as usize, y as usize)
+    };
+
+    let mut svg = open_document(width, height, options.module_size, options.responsive, "geometricPrecision");
+
+    let warnings = check_contrast(options);
+
+    let eye_fill = options.eyes.fill.as_ref().unwrap_or(&options.foreground);
+    let (origin, symbol_width, symbol_height) = (quiet_zone as f64, matrix.width() as f64, matrix.height() as f64);
+    let definitions: Vec<String> = [(&options.foreground, "qr-modules"), (eye_fill, "qr-eyes")].iter()
+        .filter_map(|(fill, id)| fill.definition(id, origin, origin, symbol_width, symbol_height))
+        .collect();
+    if !definitions.is_empty() {
+        svg.push_str("<defs>\n");
+        for definition in definitions { svg.push_str(&definition); }
+        svg.push_str("</defs>\n");
+    }
+
+    push_background(&mut svg, width, height, &options.background);
+
+    let mut modules = String::new();
+    for y in 0..matrix.height() as isize {
+        let mut x = 0;
+        while x < matrix.width() as isize {
+            if !is_dark(x, y) {
+                x += 1;
+                continue;
+            }
+
+            let (left, top) = ((x as usize + quiet_zone) as f64, (y as usize + quiet_zone) as f64);
+            match options.module_shape {
+                ModuleShape::Square => {
+                    let start = x;
+                    while is_dark(x, y) { x += 1; }
+                    modules.push_str(&format!("M{},{}h{}v1h-{}z", left, top, x - start, x - start));
+                    continue;
+                },
+                ModuleShape::Circle => modules.push_str(&rounded_rect(left, top, 1.0, 1.0, [0.5; 4])),
+                ModuleShape::Rounded => modules.push_str(&rounded_rect(left, top, 1.0, 1.0, [ROUNDED_MODULE_RADIUS; 4])),
+                ModuleShape::ConnectedRounded => {
+                    // a corner is rounded if neither of the two modules sharing its edges is dark
+                    let (up, down, left_dark, right) = (is_dark(x, y - 1), is_dark(x, y + 1), is_dark(x - 1, y), is_dark(x + 1, y));
+                    let radius = |a: bool, b: bool| if a || b { 0.0 } else { 0.5 };
+                    let radii = [radius(up, left_dark), radius(up, right), radius(down, right), radius(down, left_dark)];
+                    modules.push_str(&rounded_rect(left, top, 1.0, 1.0, radii));
+                },
+            }
+            x += 1;
+        }
+    }
+    svg.push_str(&format!("<path fill=\"{}\" d=\"{}\"/>\n", options.foreground.paint("qr-modules"), modules));
+
+    // the dark ring is the area between its outline and the light ring, both drawn as subpaths of the even odd path
+    let mut eyes = String::new();
+    for (x, y, size) in finders {
+        let (left, top, size) = ((x + quiet_zone) as f64, (y + quiet_zone) as f64, size as f64);
+
+        eyes.push_str(&rounded_rect(left, top, size, size, [0.0; 4]));
+        eyes.push_str(&rounded_rect(left + 1.0, top + 1.0, size - 2.0, size - 2.0, [0.0; 4]));
+        eyes.push_str(&rounded_rect(left + 2.0, top + 2.0, size - 4.0, size - 4.0, [0.0; 4]));
+    }
+    svg.push_str(&format!("<path fill=\"{}\" fill-rule=\"evenodd\" d=\"{}\"/>\n", eye_fill.paint("qr-eyes"), eyes));
+
+    svg.push_str("</svg>\n");
+
+    StyledSvg { svg, warnings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    fn eye_path(svg: &str) -> &str {
+        svg.lines().find(|line| line.contains("evenodd")).unwrap()
+    }
+
+    fn styled(options: &StyledSvgOptions) -> StyledSvg {
+        let matrix = MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap();
+        to_styled_svg(&matrix, options)
+    }
+
+    #[test]
+    fn finder_stays_square_with_rounded_modules() {
+        let styled = styled(&StyledSvgOptions { quiet_zone: Some(0), module_shape: ModuleShape::Circle, ..Default::default() });
+        assert_eq!(eye_path(&styled.svg), "<path fill=\"#000000\" fill-rule=\"evenodd\" d=\"M0,0h7v7h-7v-7zM1,1h5v5h-5v-5zM2,2h3v3h-3v-3z\"/>");
+        assert!(styled.warnings.is_empty());
+    }
+
+    #[test]
+    fn eye_fill_is_checked_like_the_modules() {
+        let options = StyledSvgOptions {
+            quiet_zone: Some(0),
+            eyes: EyeStyle { fill: Some(Fill::Solid(String::from("#aaaaaa"))) },
+            ..Default::default()
+        };
+        let styled = styled(&options);
+
+        assert_eq!(eye_path(&styled.svg), "<path fill=\"#aaaaaa\" fill-rule=\"evenodd\" d=\"M0,0h7v7h-7v-7zM1,1h5v5h-5v-5zM2,2h3v3h-3v-3z\"/>");
+        assert!(matches!(&styled.warnings[..], [StyleWarning::LowContrast { color, .. }] if color == "#aaaaaa"));
+    }
+
+    #[test]
+    fn contrast_below_wcag_minimum_is_reported() {
+        let with_foreground = |color: &str| StyledSvgOptions { foreground: Fill::Solid(String::from(color)), ..Default::default() };
+
+        // #777777 reaches 4.48:1 on white and #767676 4.54:1
+        assert!(matches!(check_contrast(&with_foreground("#777777"))[..], [StyleWarning::LowContrast { .. }]));
+        assert!(check_contrast(&with_foreground("#767676")).is_empty());
+        assert_eq!(check_contrast(&with_foreground("#ffffff")), [StyleWarning::LowContrast { color: String::from("#ffffff"), ratio: 1.0 }]);
+    }
+}
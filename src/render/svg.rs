@@ -0,0 +1,141 @@
+use crate::matrix::QRMatrix;
+use crate::render::dark_runs;
+
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    // edge length of a module in user units, ignored by the responsive mode
+    pub module_size: usize,
+    // width of the light border in modules, the minimum of the symbol type if None
+    pub quiet_zone: Option<usize>,
+    pub foreground: String,
+    // no background rectangle is drawn if None
+    pub background: Option<String>,
+    // only sets the viewBox so the image scales to its container
+    pub responsive: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            module_size: 8,
+            quiet_zone: None,
+            foreground: String::from("#000000"),
+            background: Some(String::from("#ffffff")),
+            responsive: false,
+        }
+    }
+}
+
+pub(crate) fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+// xml declaration and opening svg tag, the view box spans width x height modules
+pub(crate) fn open_document(width: usize, height: usize, module_size: usize, responsive: bool, shape_rendering: &str) -> String {
+    let mut svg = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    svg.push_str("<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\"");
+    if !responsive {
+        svg.push_str(&format!(" width=\"{}\" height=\"{}\"", width * module_size, height * module_size));
+    }
+    svg.push_str(&format!(" viewBox=\"0 0 {} {}\" shape-rendering=\"{}\">\n", width, height, shape_rendering));
+
+    svg
+}
+
+pub(crate) fn push_background(svg: &mut String, width: usize, height: usize, background: &Option<String>) {
+    if let Some(background) = background {
+        svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", width, height, escape_attribute(background)));
+    }
+}
+
+// the symbol as SVG document, all dark modules are drawn by a single path with one subpath per horizontal run
+// the coordinates are in modules and scaled to the module size by the width and height of the document
+pub fn to_svg(matrix: &QRMatrix, options: &SvgOptions) -> String {
+    let quiet_zone = options.quiet_zone.unwrap_or_else(|| matrix.quiet_zone());
+    let width = matrix.width() + 2 * quiet_zone;
+    let height = matrix.height() + 2 * quiet_zone;
+
+    let mut svg = open_document(width, height, options.module_size, options.responsive, "crispEdges");
+    push_background(&mut svg, width, height, &options.background);
+
+    let mut path = String::new();
+    for y in 0..matrix.height() {
+        for (x, len) in dark_runs(matrix, y) {
+            path.push_str(&format!("M{},{}h{}v1h-{}z", x + quiet_zone, y + quiet_zone, len, len));
+        }
+    }
+    svg.push_str(&format!("<path fill=\"{}\" d=\"{}\"/>\n", escape_attribute(&options.foreground), path));
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the M1 symbol of "1", 11 x 11 modules
+    // #######.#.#
+    // #.....#.#..
+    // #.###.#..##
+    // #.###.#.#..
+    // #.###.#..##
+    // #.....#....
+    // #######..##
+    // ........#.#
+    // ##...#...##
+    // .#.######.#
+    // ########.##
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    #[test]
+    fn runs_of_each_row_are_merged_into_one_path() {
+        let options = SvgOptions { quiet_zone: Some(0), ..Default::default() };
+        let expected = concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"88\" height=\"88\" viewBox=\"0 0 11 11\" shape-rendering=\"crispEdges\">\n",
+            "<rect width=\"11\" height=\"11\" fill=\"#ffffff\"/>\n",
+            "<path fill=\"#000000\" d=\"",
+            "M0,0h7v1h-7zM8,0h1v1h-1zM10,0h1v1h-1z",
+            "M0,1h1v1h-1zM6,1h1v1h-1zM8,1h1v1h-1z",
+            "M0,2h1v1h-1zM2,2h3v1h-3zM6,2h1v1h-1zM9,2h2v1h-2z",
+            "M0,3h1v1h-1zM2,3h3v1h-3zM6,3h1v1h-1zM8,3h1v1h-1z",
+            "M0,4h1v1h-1zM2,4h3v1h-3zM6,4h1v1h-1zM9,4h2v1h-2z",
+            "M0,5h1v1h-1zM6,5h1v1h-1z",
+            "M0,6h7v1h-7zM9,6h2v1h-2z",
+            "M8,7h1v1h-1zM10,7h1v1h-1z",
+            "M0,8h2v1h-2zM5,8h1v1h-1zM9,8h2v1h-2z",
+            "M1,9h1v1h-1zM3,9h6v1h-6zM10,9h1v1h-1z",
+            "M0,10h8v1h-8zM9,10h2v1h-2z",
+            "\"/>\n",
+            "</svg>\n",
+        );
+
+        assert_eq!(to_svg(&symbol(), &options), expected);
+    }
+
+    #[test]
+    fn quiet_zone_moves_the_path_and_responsive_drops_the_size() {
+        let options = SvgOptions {
+            quiet_zone: None,
+            foreground: String::from("url(\"#a\")&<"),
+            background: None,
+            responsive: true,
+            ..Default::default()
+        };
+        let svg = to_svg(&symbol(), &options);
+
+        // micro symbols need a quiet zone of 2 modules
+        assert!(svg.starts_with(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 15 15\" shape-rendering=\"crispEdges\">\n",
+            "<path fill=\"url(&quot;#a&quot;)&amp;&lt;\" d=\"M2,2h7v1h-7zM10,2h1v1h-1zM12,2h1v1h-1z",
+        )));
+        assert!(svg.ends_with("M2,12h8v1h-8zM11,12h2v1h-2z\"/>\n</svg>\n"));
+        assert!(!svg.contains("<rect"));
+    }
+}
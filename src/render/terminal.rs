@@ -0,0 +1,168 @@
+use crate::matrix::QRMatrix;
+use crate::render::get_with_quiet_zone;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TerminalColor {
+    // keeps the colors of the terminal
+    #[default]
+    None,
+    // indices into the 256 color palette
+    Ansi256 { foreground: u8, background: u8 },
+    TrueColor { foreground: (u8, u8, u8), background: (u8, u8, u8) },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TerminalOptions {
+    // width of the light border in modules, the minimum of the symbol type if None
+    pub quiet_zone: Option<usize>,
+    // draws the light instead of the dark modules, for terminals with light text on a dark background
+    pub inverted: bool,
+    pub color: TerminalColor,
+    // two characters per module and one line per row instead of the half block characters
+    pub ascii: bool,
+}
+
+// escape sequence switching to the foreground and background color, empty if the terminal colors are kept
+fn color_escape(color: &TerminalColor) -> String {
+    match color {
+        TerminalColor::None => String::new(),
+        TerminalColor::Ansi256 { foreground, background } => format!("\x1b[38;5;{}m\x1b[48;5;{}m", foreground, background),
+        TerminalColor::TrueColor { foreground: (fr, fg, fb), background: (br, bg, bb) } =>
+            format!("\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m", fr, fg, fb, br, bg, bb),
+    }
+}
+
+// the symbol as text, the half block characters pack two rows of modules into each line
+// a symbol with an odd number of rows gets an additional light row at the bottom
+pub fn to_terminal(matrix: &QRMatrix, options: &TerminalOptions) -> String {
+    let quiet_zone = options.quiet_zone.unwrap_or_else(|| matrix.quiet_zone());
+    let width = matrix.width() + 2 * quiet_zone;
+    let height = matrix.height() + 2 * quiet_zone;
+
+    // the drawn modules are printed in the foreground color, all others are left as background
+    let drawn = |x: usize, y: usize| get_with_quiet_zone(matrix, quiet_zone, x, y) != options.inverted;
+
+    let (start, reset) = match options.color {
+        TerminalColor::None => (String::new(), ""),
+        color               => (color_escape(&color), "\x1b[0m"),
+    };
+
+    let mut text = String::new();
+    let rows_per_line = if options.ascii { 1 } else { 2 };
+
+    for y in (0..height).step_by(rows_per_line) {
+        text.push_str(&start);
+
+        for x in 0..width {
+            if options.ascii {
+                text.push_str(if drawn(x, y) { "##" } else { "  " });
+                continue;
+            }
+
+            // the row below the symbol is light
+            let bottom = if y + 1 < height { drawn(x, y + 1) } else { options.inverted };
+            text.push(match (drawn(x, y), bottom) {
+                (true, true)   => '█',
+                (true, false)  => '▀',
+                (false, true)  => '▄',
+                (false, false) => ' ',
+            });
+        }
+
+        text.push_str(reset);
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the M1 symbol of "1", 11 x 11 modules
+    // #######.#.#
+    // #.....#.#..
+    // #.###.#..##
+    // #.###.#.#..
+    // #.###.#..##
+    // #.....#....
+    // #######..##
+    // ........#.#
+    // ##...#...##
+    // .#.######.#
+    // ########.##
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    fn options(inverted: bool, color: TerminalColor, ascii: bool) -> TerminalOptions {
+        TerminalOptions { quiet_zone: Some(0), inverted, color, ascii }
+    }
+
+    // the odd last row is paired with a light row
+    #[test]
+    fn half_blocks_with_odd_height() {
+        let expected = concat!(
+            "█▀▀▀▀▀█ █ ▀\n",
+            "█ ███ █ ▄▀▀\n",
+            "█ ▀▀▀ █  ▀▀\n",
+            "▀▀▀▀▀▀▀ ▄▀█\n",
+            "▀█ ▄▄█▄▄▄▀█\n",
+            "▀▀▀▀▀▀▀▀ ▀▀\n",
+        );
+
+        assert_eq!(to_terminal(&symbol(), &options(false, TerminalColor::None, false)), expected);
+    }
+
+    // the light row below the symbol is drawn as well when inverted
+    #[test]
+    fn inverted_half_blocks() {
+        let expected = concat!(
+            " ▄▄▄▄▄ █ █▄\n",
+            " █   █ █▀▄▄\n",
+            " █▄▄▄█ ██▄▄\n",
+            "▄▄▄▄▄▄▄█▀▄ \n",
+            "▄ █▀▀ ▀▀▀▄ \n",
+            "▄▄▄▄▄▄▄▄█▄▄\n",
+        );
+
+        assert_eq!(to_terminal(&symbol(), &options(true, TerminalColor::None, false)), expected);
+    }
+
+    #[test]
+    fn ascii_fallback() {
+        let expected = concat!(
+            "##############  ##  ##\n",
+            "##          ##  ##    \n",
+            "##  ######  ##    ####\n",
+            "##  ######  ##  ##    \n",
+            "##  ######  ##    ####\n",
+            "##          ##        \n",
+            "##############    ####\n",
+            "                ##  ##\n",
+            "####      ##      ####\n",
+            "  ##  ############  ##\n",
+            "################  ####\n",
+        );
+
+        assert_eq!(to_terminal(&symbol(), &options(false, TerminalColor::None, true)), expected);
+    }
+
+    #[test]
+    fn colors_are_set_and_reset_on_every_line() {
+        let ansi = to_terminal(&symbol(), &options(false, TerminalColor::Ansi256 { foreground: 16, background: 231 }, false));
+        let lines: Vec<&str> = ansi.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "\x1b[38;5;16m\x1b[48;5;231m█▀▀▀▀▀█ █ ▀\x1b[0m");
+        assert!(lines.iter().all(|line| line.starts_with("\x1b[38;5;16m\x1b[48;5;231m") && line.ends_with("\x1b[0m")));
+
+        let color = TerminalColor::TrueColor { foreground: (0, 0, 0), background: (255, 255, 255) };
+        let true_color = to_terminal(&symbol(), &TerminalOptions { quiet_zone: None, color, ..Default::default() });
+        let first = true_color.lines().next().unwrap();
+        assert_eq!(first, format!("\x1b[38;2;0;0;0m\x1b[48;2;255;255;255m{}\x1b[0m", " ".repeat(15)));
+        assert_eq!(true_color.lines().count(), 8);
+    }
+}
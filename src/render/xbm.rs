@@ -0,0 +1,66 @@
+use crate::matrix::QRMatrix;
+use crate::render::{Raster, RasterOptions};
+
+// the bytes of an X11 bitmap are written 12 per line like the bitmap tool does
+const BYTES_PER_LINE: usize = 12;
+
+// X11 bitmap as C source, the name prefixes the width, height and bits identifiers
+// 8 pixels per byte with the first pixel in the least significant bit, set bits are dark
+pub fn to_xbm(matrix: &QRMatrix, options: &RasterOptions, name: &str) -> String {
+    let raster = Raster::new(matrix, options);
+
+    let mut bytes = Vec::with_capacity(raster.width().div_ceil(8) * raster.height());
+    for py in 0..raster.height() {
+        bytes.extend(raster.packed_row(py).iter().map(|byte| byte.reverse_bits()));
+    }
+
+    let mut xbm = format!("#define {}_width {}\n#define {}_height {}\n", name, raster.width(), name, raster.height());
+    xbm.push_str(&format!("static unsigned char {}_bits[] = {{\n", name));
+
+    let lines: Vec<String> = bytes.chunks(BYTES_PER_LINE)
+        .map(|line| {
+            let values: Vec<String> = line.iter().map(|byte| format!("0x{:02x}", byte)).collect();
+            format!("   {}", values.join(", "))
+        })
+        .collect();
+    xbm.push_str(&lines.join(",\n"));
+
+    xbm.push_str(" };\n");
+    xbm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::micro::MicroQRCode;
+    use crate::qrcode::ErrorCorrectionLevel;
+
+    // the 11 x 11 M1 symbol of "1", the same one as in the netpbm tests
+    fn symbol() -> QRMatrix {
+        MicroQRCode::new(String::from("1"), ErrorCorrectionLevel::L).unwrap().gen_matrix().unwrap()
+    }
+
+    #[test]
+    fn xbm() {
+        let expected = "#define m1_width 11\n#define m1_height 11\nstatic unsigned char m1_bits[] = {\n   \
+            0x7f, 0x05, 0x41, 0x01, 0x5d, 0x06, 0x5d, 0x01, 0x5d, 0x06, 0x41, 0x00,\n   \
+            0x7f, 0x06, 0x00, 0x05, 0x23, 0x06, 0xfa, 0x05, 0xff, 0x06 };\n";
+        assert_eq!(to_xbm(&symbol(), &RasterOptions { scale: 1, quiet_zone: Some(0) }, "m1"), expected);
+    }
+
+    // 26 pixels per row are padded to 4 bytes
+    #[test]
+    fn xbm_scaled_with_quiet_zone() {
+        let expected = "#define m1_width 26\n#define m1_height 26\nstatic unsigned char m1_bits[] = {\n   \
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfc, 0xff, 0xcc, 0x00,\n   \
+            0xfc, 0xff, 0xcc, 0x00, 0x0c, 0xc0, 0x0c, 0x00, 0x0c, 0xc0, 0x0c, 0x00,\n   \
+            0xcc, 0xcf, 0xf0, 0x00, 0xcc, 0xcf, 0xf0, 0x00, 0xcc, 0xcf, 0x0c, 0x00,\n   \
+            0xcc, 0xcf, 0x0c, 0x00, 0xcc, 0xcf, 0xf0, 0x00, 0xcc, 0xcf, 0xf0, 0x00,\n   \
+            0x0c, 0xc0, 0x00, 0x00, 0x0c, 0xc0, 0x00, 0x00, 0xfc, 0xff, 0xf0, 0x00,\n   \
+            0xfc, 0xff, 0xf0, 0x00, 0x00, 0x00, 0xcc, 0x00, 0x00, 0x00, 0xcc, 0x00,\n   \
+            0x3c, 0x30, 0xf0, 0x00, 0x3c, 0x30, 0xf0, 0x00, 0x30, 0xff, 0xcf, 0x00,\n   \
+            0x30, 0xff, 0xcf, 0x00, 0xfc, 0xff, 0xf3, 0x00, 0xfc, 0xff, 0xf3, 0x00,\n   \
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00 };\n";
+        assert_eq!(to_xbm(&symbol(), &RasterOptions { scale: 2, quiet_zone: Some(1) }, "m1"), expected);
+    }
+}
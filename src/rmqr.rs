@@ -0,0 +1,490 @@
+use crate::matrix::QRMatrix;
+use crate::qrcode::{QRCodeError, QREncoding, ErrorCorrectionLevel, ErrorCorrectionMetaData, encode_data, find_encoding,
+    split_blocks, gen_block_error_codewords, interleave_blocks};
+use crate::segment::Segment;
+
+// source: ISO/IEC 23941, rectangular Micro QR Code (rMQR) bar code symbology specification
+
+// the 32 rMQR versions R7x43 to R17x139, the version number is the position in the table starting at 1
+// 0: height
+// 1: width
+// 2: remainder bits after the final codewords
+// 3: character count bits for numeric, alphanumeric, byte and kanji mode
+const RMQR_VERSIONS: [(usize, usize, usize, [usize; 4]); 32] = [
+    (7,  43,  0, [4,3,3,2]),
+    (7,  59,  3, [5,5,4,3]),
+    (7,  77,  5, [6,5,5,4]),
+    (7,  99,  6, [7,6,5,5]),
+    (7,  139, 1, [7,6,6,5]),
+    (9,  43,  2, [5,5,4,3]),
+    (9,  59,  3, [6,5,5,4]),
+    (9,  77,  1, [7,6,5,5]),
+    (9,  99,  4, [7,6,6,5]),
+    (9,  139, 5, [8,7,6,6]),
+    (11, 27,  2, [4,4,3,2]),
+    (11, 43,  1, [6,5,5,4]),
+    (11, 59,  0, [7,6,5,5]),
+    (11, 77,  2, [7,6,6,5]),
+    (11, 99,  7, [8,7,6,6]),
+    (11, 139, 6, [8,7,7,6]),
+    (13, 27,  4, [5,5,4,3]),
+    (13, 43,  1, [6,6,5,5]),
+    (13, 59,  6, [7,6,6,5]),
+    (13, 77,  4, [7,7,6,6]),
+    (13, 99,  3, [8,7,7,6]),
+    (13, 139, 0, [8,8,7,7]),
+    (15, 43,  1, [7,6,6,5]),
+    (15, 59,  4, [7,7,6,5]),
+    (15, 77,  6, [8,7,7,6]),
+    (15, 99,  7, [8,7,7,6]),
+    (15, 139, 2, [9,8,7,7]),
+    (17, 43,  1, [7,6,6,5]),
+    (17, 59,  2, [8,7,6,6]),
+    (17, 77,  0, [8,7,7,6]),
+    (17, 99,  3, [8,8,7,6]),
+    (17, 139, 4, [9,8,8,7]),
+];
+
+// width and height of the symbol of the version
+pub fn get_rmqr_dimensions(version: usize) -> (usize, usize) {
+    let (height, width, _, _) = RMQR_VERSIONS[version - 1];
+    (width, height)
+}
+
+// capacities of the 32 versions in the order of RMQR_VERSIONS, rMQR only supports the levels M and H
+const RMQR_CHAR_CAPACITY: [(QREncoding, ErrorCorrectionLevel, [usize; 32]); 8]
+    = [
+        (QREncoding::Numeric,       ErrorCorrectionLevel::M, [12,26,45,64,102,26,47,71,97,147,14,42,71,100,133,198,26,62,88,124,171,251,76,112,157,207,301,90,131,183,236,361]),
+        (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::M, [7,16,27,39,62,16,29,43,59,89,8,26,43,60,81,120,16,37,53,75,104,152,46,68,95,126,182,55,79,111,143,219]),
+        (QREncoding::Byte,          ErrorCorrectionLevel::M, [5,11,19,27,42,11,20,30,40,61,6,18,30,41,55,82,11,26,36,51,71,104,31,46,65,86,125,37,54,76,98,150]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::M, [3,6,11,16,26,6,12,18,25,38,3,11,18,25,34,51,6,16,22,31,44,64,19,28,40,53,77,23,33,47,60,92]),
+        (QREncoding::Numeric,       ErrorCorrectionLevel::H, [5,14,21,30,54,14,23,37,49,75,9,23,33,52,66,97,14,28,45,66,80,126,33,59,71,111,162,47,63,87,131,178]),
+        (QREncoding::AlphaNumeric,  ErrorCorrectionLevel::H, [3,8,13,18,33,8,14,23,30,46,6,14,20,31,40,59,8,17,27,40,49,76,20,36,43,68,98,28,38,53,79,108]),
+        (QREncoding::Byte,          ErrorCorrectionLevel::H, [2,6,9,13,22,6,10,16,20,31,4,10,14,21,27,40,6,12,18,27,33,52,13,24,29,46,67,19,26,36,54,74]),
+        (QREncoding::Kanji,         ErrorCorrectionLevel::H, [1,3,5,8,14,3,6,9,12,19,2,6,8,13,17,25,3,7,11,17,20,32,8,15,18,28,41,12,16,22,33,46]),
+    ];
+
+// the six symbol heights, every height has versions of several widths
+const RMQR_HEIGHTS: [usize; 6] = [7, 9, 11, 13, 15, 17];
+
+// looks up the version with the smallest area that holds the data, only considering the given height if there is one
+// 0 if no version fits
+fn get_rmqr_size(data_size: usize, encoding: &QREncoding, error_correction: &ErrorCorrectionLevel, height: Option<usize>) -> usize {
+    let current_option = (encoding, error_correction);
+
+    for option in RMQR_CHAR_CAPACITY {
+        if &option.0 == current_option.0 && &option.1 == current_option.1 {
+            return (1..=32)
+                .filter(|&version| height.is_none_or(|height| RMQR_VERSIONS[version - 1].0 == height))
+                .filter(|&version| data_size <= option.2[version - 1])
+                .min_by_key(|&version| {
+                    let (width, height) = get_rmqr_dimensions(version);
+                    width * height
+                })
+                .unwrap_or(0);
+        }
+    }
+
+    0
+}
+
+// same layout as ERROR_CORRECTION_DATA of the regular versions
+// 0: codewords per block
+// 1: number of blocks in group 1
+// 2: number of data codewords in each of group 1's blocks
+// 3: number of blocks in group 2
+// 4: number of data codewords in each of group 2's blocks
+const RMQR_ERROR_CORRECTION_DATA: [(usize, ErrorCorrectionLevel, [usize; 5]); 64] = [
+    (1,  ErrorCorrectionLevel::M, [7,1,6,0,0]),
+    (1,  ErrorCorrectionLevel::H, [10,1,3,0,0]),
+    (2,  ErrorCorrectionLevel::M, [9,1,12,0,0]),
+    (2,  ErrorCorrectionLevel::H, [14,1,7,0,0]),
+    (3,  ErrorCorrectionLevel::M, [12,1,20,0,0]),
+    (3,  ErrorCorrectionLevel::H, [22,1,10,0,0]),
+    (4,  ErrorCorrectionLevel::M, [16,1,28,0,0]),
+    (4,  ErrorCorrectionLevel::H, [30,1,14,0,0]),
+    (5,  ErrorCorrectionLevel::M, [24,1,44,0,0]),
+    (5,  ErrorCorrectionLevel::H, [22,2,12,0,0]),
+    (6,  ErrorCorrectionLevel::M, [9,1,12,0,0]),
+    (6,  ErrorCorrectionLevel::H, [14,1,7,0,0]),
+    (7,  ErrorCorrectionLevel::M, [12,1,21,0,0]),
+    (7,  ErrorCorrectionLevel::H, [22,1,11,0,0]),
+    (8,  ErrorCorrectionLevel::M, [18,1,31,0,0]),
+    (8,  ErrorCorrectionLevel::H, [16,1,8,1,9]),
+    (9,  ErrorCorrectionLevel::M, [24,1,42,0,0]),
+    (9,  ErrorCorrectionLevel::H, [22,2,11,0,0]),
+    (10, ErrorCorrectionLevel::M, [18,1,31,1,32]),
+    (10, ErrorCorrectionLevel::H, [22,3,11,0,0]),
+    (11, ErrorCorrectionLevel::M, [8,1,7,0,0]),
+    (11, ErrorCorrectionLevel::H, [10,1,5,0,0]),
+    (12, ErrorCorrectionLevel::M, [12,1,19,0,0]),
+    (12, ErrorCorrectionLevel::H, [20,1,11,0,0]),
+    (13, ErrorCorrectionLevel::M, [16,1,31,0,0]),
+    (13, ErrorCorrectionLevel::H, [16,1,7,1,8]),
+    (14, ErrorCorrectionLevel::M, [24,1,43,0,0]),
+    (14, ErrorCorrectionLevel::H, [22,1,11,1,12]),
+    (15, ErrorCorrectionLevel::M, [16,1,28,1,29]),
+    (15, ErrorCorrectionLevel::H, [30,1,14,1,15]),
+    (16, ErrorCorrectionLevel::M, [24,2,42,0,0]),
+    (16, ErrorCorrectionLevel::H, [30,3,14,0,0]),
+    (17, ErrorCorrectionLevel::M, [9,1,12,0,0]),
+    (17, ErrorCorrectionLevel::H, [14,1,7,0,0]),
+    (18, ErrorCorrectionLevel::M, [14,1,27,0,0]),
+    (18, ErrorCorrectionLevel::H, [28,1,13,0,0]),
+    (19, ErrorCorrectionLevel::M, [22,1,38,0,0]),
+    (19, ErrorCorrectionLevel::H, [20,2,10,0,0]),
+    (20, ErrorCorrectionLevel::M, [16,1,26,1,27]),
+    (20, ErrorCorrectionLevel::H, [28,1,14,1,15]),
+    (21, ErrorCorrectionLevel::M, [20,1,36,1,37]),
+    (21, ErrorCorrectionLevel::H, [26,1,11,2,12]),
+    (22, ErrorCorrectionLevel::M, [20,2,35,1,36]),
+    (22, ErrorCorrectionLevel::H, [28,2,13,2,14]),
+    (23, ErrorCorrectionLevel::M, [18,1,33,0,0]),
+    (23, ErrorCorrectionLevel::H, [18,1,7,1,8]),
+    (24, ErrorCorrectionLevel::M, [26,1,48,0,0]),
+    (24, ErrorCorrectionLevel::H, [24,2,13,0,0]),
+    (25, ErrorCorrectionLevel::M, [18,1,33,1,34]),
+    (25, ErrorCorrectionLevel::H, [24,2,10,1,11]),
+    (26, ErrorCorrectionLevel::M, [24,2,44,0,0]),
+    (26, ErrorCorrectionLevel::H, [22,4,12,0,0]),
+    (27, ErrorCorrectionLevel::M, [24,2,42,1,43]),
+    (27, ErrorCorrectionLevel::H, [26,1,13,4,14]),
+    (28, ErrorCorrectionLevel::M, [22,1,39,0,0]),
+    (28, ErrorCorrectionLevel::H, [20,1,10,1,11]),
+    (29, ErrorCorrectionLevel::M, [16,2,28,0,0]),
+    (29, ErrorCorrectionLevel::H, [30,2,14,0,0]),
+    (30, ErrorCorrectionLevel::M, [22,2,39,0,0]),
+    (30, ErrorCorrectionLevel::H, [28,1,12,2,13]),
+    (31, ErrorCorrectionLevel::M, [20,2,33,1,34]),
+    (31, ErrorCorrectionLevel::H, [26,4,14,0,0]),
+    (32, ErrorCorrectionLevel::M, [20,4,38,0,0]),
+    (32, ErrorCorrectionLevel::H, [26,2,12,4,13]),
+];
+
+fn get_rmqr_err_metadata(version: usize, error_correction: &ErrorCorrectionLevel) -> Option<ErrorCorrectionMetaData> {
+    let current_option = (version, error_correction);
+    for option in RMQR_ERROR_CORRECTION_DATA.iter() {
+        if option.0 == current_option.0 && &option.1 == current_option.1 {
+            return Some(ErrorCorrectionMetaData::from(&option.2[..]));
+        }
+    }
+
+    None
+}
+
+// rMQR uses 3 bit mode indicators, 000 is the terminator
+fn get_rmqr_encoding(encoding: &QREncoding) -> Result<&'static str, QRCodeError> {
+    match encoding {
+        QREncoding::Numeric      => Ok("001"),
+        QREncoding::AlphaNumeric => Ok("010"),
+        QREncoding::Byte         => Ok("011"),
+        QREncoding::Kanji        => Ok("100"),
+        _                        => Err(QRCodeError::UnsupportedCharacter),
+    }
+}
+
+fn get_rmqr_count_bits(encoding: &QREncoding, version: usize) -> Result<usize, QRCodeError> {
+    let widths = RMQR_VERSIONS.get(version.wrapping_sub(1)).ok_or(QRCodeError::UnsupportedVersion)?.3;
+
+    match encoding {
+        QREncoding::Numeric      => Ok(widths[0]),
+        QREncoding::AlphaNumeric => Ok(widths[1]),
+        QREncoding::Byte         => Ok(widths[2]),
+        QREncoding::Kanji        => Ok(widths[3]),
+        _                        => Err(QRCodeError::UnsupportedCharacter),
+    }
+}
+
+#[derive(Debug)]
+pub struct RMQRCode {
+    raw_data: String,
+    segment: Segment,
+    error_correction: ErrorCorrectionLevel,
+    version: usize,
+    err_metadata: ErrorCorrectionMetaData,
+}
+
+impl RMQRCode {
+    // encodes all of the data in the single mode that can represent every character, in the version with the smallest area
+    pub fn new(data: String, error_correction: ErrorCorrectionLevel) -> Result<Self, String> {
+        Self::new_with_height(data, error_correction, None)
+    }
+
+    // like new, but only versions of the given height of 7, 9, 11, 13, 15 or 17 modules are considered
+    pub fn new_with_height(data: String, error_correction: ErrorCorrectionLevel, height: Option<usize>) -> Result<Self, String> {
+        if !matches!(error_correction, ErrorCorrectionLevel::M | ErrorCorrectionLevel::H) {
+            return Err(String::from("rMQR only supports the error correction levels M and H!"))
+        }
+        if height.is_some_and(|height| !RMQR_HEIGHTS.contains(&height)) {
+            return Err(String::from("Invalid rMQR height, must be 7, 9, 11, 13, 15 or 17!"))
+        }
+
+        let segment = Segment::new(find_encoding(&data), data.clone());
+        let version = get_rmqr_size(segment.char_count(), &segment.encoding, &error_correction, height);
+
+        if version == 0 {
+            return Err(String::from("Data too long for rMQR encoding!"))
+        }
+
+        let err_metadata = get_rmqr_err_metadata(version, &error_correction).unwrap();
+        Ok(RMQRCode {
+            raw_data: data,
+            segment,
+            error_correction,
+            version,
+            err_metadata,
+        })
+    }
+
+    pub fn raw_data(&self) -> &str {
+        &self.raw_data
+    }
+
+    pub fn segment(&self) -> &Segment {
+        &self.segment
+    }
+
+    // position of the version in R7x43 to R17x139 starting at 1, the format information stores it minus one
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    // width and height of the symbol in modules
+    pub fn dimensions(&self) -> (usize, usize) {
+        get_rmqr_dimensions(self.version)
+    }
+
+    pub fn error_correction(&self) -> &ErrorCorrectionLevel {
+        &self.error_correction
+    }
+
+    pub fn encode(&self) -> Result<String, QRCodeError> {
+        let mut bit_buffer = String::new();
+
+        bit_buffer.push_str(get_rmqr_encoding(&self.segment.encoding)?);
+
+        let width = get_rmqr_count_bits(&self.segment.encoding, self.version)?;
+        bit_buffer.push_str(&format!("{:0width$b}", self.segment.char_count(), width = width));
+
+        encode_data(&self.segment, &mut bit_buffer);
+
+        self.add_padding(&mut bit_buffer);
+
+        Ok(bit_buffer)
+    }
+
+    fn add_padding(&self, bit_buffer: &mut String) {
+        let bit_size = self.err_metadata.total_code_words() * 8;
+
+        // add terminator of 0s => at most three 0s
+        let terminator_len = 3.min(bit_size - bit_buffer.len());
+        for _ in 0..terminator_len { bit_buffer.push('0'); }
+
+        // pad to multiple of 8
+        let diff_to_eight = match bit_buffer.len() % 8 {
+            0 => 0,
+            val => 8 - val,
+        };
+        for _ in 0..diff_to_eight { bit_buffer.push('0'); }
+
+        // add padding to reach maximum data lenght
+        let missing_bytes = (bit_size - bit_buffer.len()) / 8;
+        let padding_bytes = ["11101100", "00010001"];
+        for i in 0..missing_bytes {
+            bit_buffer.push_str(padding_bytes[i % 2]);
+        }
+    }
+
+    pub fn get_data_codewords(&self) -> Result<Vec<u8>, QRCodeError> {
+        let bitbuf = self.encode()?;
+        let chunks = bitbuf.len() / 8;
+
+        let mut codewords = vec![0; chunks];
+        for (i, codeword) in codewords.iter_mut().enumerate() {
+            let byte = &bitbuf[i*8..(i + 1)*8];
+            *codeword = u8::from_str_radix(byte, 2)
+                                .expect("This should be a valid bin string!");
+        }
+
+        Ok(codewords)
+    }
+
+    pub fn get_data_blocks(&self) -> Result<Vec<Vec<u8>>, QRCodeError> {
+        Ok(split_blocks(&self.get_data_codewords()?, &self.err_metadata))
+    }
+
+    pub fn gen_error_codewords(&self) -> Result<Vec<Vec<u8>>, QRCodeError> {
+        Ok(gen_block_error_codewords(&self.get_data_blocks()?, self.err_metadata.words_per_block))
+    }
+
+    // interleaved data and error correction codewords followed by the remainder bits, like in regular symbols
+    pub fn final_message(&self) -> Result<String, QRCodeError> {
+        let mut message = interleave_blocks(&self.get_data_blocks()?, &self.gen_error_codewords()?);
+        for _ in 0..RMQR_VERSIONS[self.version - 1].2 { message.push('0'); }
+
+        Ok(message)
+    }
+
+    // finished symbol grid with all function patterns, the masked final message and both copies of the format information
+    // rMQR has a single mask pattern, so no mask has to be chosen
+    pub fn gen_matrix(&self) -> Result<QRMatrix, QRCodeError> {
+        let mut matrix = QRMatrix::new_rmqr(self.version);
+        matrix.place_data(&self.final_message()?);
+        matrix.apply_mask(0);
+        matrix.place_rmqr_format_info(&self.error_correction, self.version - 1);
+
+        Ok(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // snapshots of this encoder's own output that guard against regressions, not symbols of an independent encoder
+    // when they were taken, the format information of both copies was read back and the data decoded with a separate script
+    fn rows(height: usize, error_correction: ErrorCorrectionLevel) -> Vec<String> {
+        let code = RMQRCode::new_with_height(String::from("RMQR 2024"), error_correction, Some(height)).unwrap();
+        let matrix = code.gen_matrix().unwrap();
+
+        (0..matrix.height())
+            .map(|y| (0..matrix.width()).map(|x| if matrix.get(x, y) { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn rejects_unsupported_levels() {
+        for level in [ErrorCorrectionLevel::L, ErrorCorrectionLevel::Q] {
+            let err = RMQRCode::new(String::from("RMQR"), level).unwrap_err();
+            assert_eq!(err, "rMQR only supports the error correction levels M and H!");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_heights() {
+        for height in [0, 8, 19] {
+            let err = RMQRCode::new_with_height(String::from("RMQR"), ErrorCorrectionLevel::M, Some(height)).unwrap_err();
+            assert_eq!(err, "Invalid rMQR height, must be 7, 9, 11, 13, 15 or 17!");
+        }
+    }
+
+    #[test]
+    fn too_long_data_is_still_reported() {
+        let err = RMQRCode::new_with_height("9".repeat(200), ErrorCorrectionLevel::H, Some(7)).unwrap_err();
+        assert_eq!(err, "Data too long for rMQR encoding!");
+    }
+
+    #[test]
+    fn snapshot_r7x59() {
+        let expected = [
+            "#######.#.#.#.#.#.###.#.#.#.#.#.#.#.#.###.#.#.#.#.#.#.#.###",
+            "#.....#.#.###.#####.##.#....#.#..#..###.##.#..#..##..#..#.#",
+            "#.###.#.#..#.#.######..#.####.####....#####....######.#####",
+            "#.###.#.##..#...#...###.##..##...##.....##.##.#....#.##...#",
+            "#.###.#..##..#.#.######.#####.##..##.####.#####...###.#.#.#",
+            "#.....#.#.#.#.#..##.#...#......##...###.#.##..#.##.#..#...#",
+            "#######.#.#.#.#.#.###.#.#.#.#.#.#.#.#.###.#.#.#.#.#.#.#####",
+        ];
+        assert_eq!(rows(7, ErrorCorrectionLevel::M), expected);
+    }
+
+    #[test]
+    fn snapshot_r9x43() {
+        let expected = [
+            "#######.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#.###",
+            "#.....#.#.##.#.....##.####.....#...#.###..#",
+            "#.###.#..#.#.###.#.#####..#.###...#..####.#",
+            "#.###.#.....#.#.#..##.##.#####.##....#..##.",
+            "#.###.#.###..#..#..###.#.###.#####....#####",
+            "#.....#..#..##...##...#...#...##..#.###...#",
+            "#######..##.####....######.###..##..#.#.#.#",
+            "........##..#..##.###.##.....#..###.###...#",
+            "###.#.#.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#####",
+        ];
+        assert_eq!(rows(9, ErrorCorrectionLevel::M), expected);
+    }
+
+    #[test]
+    fn snapshot_r11x43() {
+        let expected = [
+            "#######.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#.###",
+            "#.....#.#....#....#.#.#.#.#.###.#..##.###.#",
+            "#.###.#...##.#####..###......#...#....#####",
+            "#.###.#..#.##..###.##..#..###...##.###...#.",
+            "#.###.#.......##.#...#.#..#.#.###.###.##.##",
+            "#.....#.#.#.#..#..#.#.########.##.....#....",
+            "#######.#....#.##.#..#...##...##.##.#######",
+            "........#####..##.##..####..###..#...##...#",
+            "##....#.##.#..#.#...#####....##.##.#.##.#.#",
+            "#......##..#.#...##.#.##.###..##..##..#...#",
+            "###.#.#.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#####",
+        ];
+        assert_eq!(rows(11, ErrorCorrectionLevel::H), expected);
+    }
+
+    #[test]
+    fn snapshot_r13x27() {
+        let expected = [
+            "#######.#.#.#.#.#.#.#.#.###",
+            "#.....#....#..##..#.##.#..#",
+            "#.###.#.##..#..#####.##...#",
+            "#.###.#..##...#.#...#.#.##.",
+            "#.###.#.###....##.##..#####",
+            "#.....#...####..####.#...#.",
+            "#######.....#....#....#..##",
+            "..........####.###.#...##..",
+            "##...########.#...##..#####",
+            ".#......##..#.##......#...#",
+            "#......##.#....##...#.#.#.#",
+            "#...#.....###...##....#...#",
+            "###.#.#.#.#.#.#.#.#.#.#####",
+        ];
+        assert_eq!(rows(13, ErrorCorrectionLevel::M), expected);
+    }
+
+    #[test]
+    fn snapshot_r15x43() {
+        let expected = [
+            "#######.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#.###",
+            "#.....#..#.#..#.#..##.##..#.#.#####..#.#..#",
+            "#.###.#.#.#.####..#####.##.###..##.#...####",
+            "#.###.#.####..##......##..#.#..##.#.#.#.##.",
+            "#.###.#.##.#####...#.#.######.##..####....#",
+            "#.....#..#.#..#.#.....#.#.###.###.##.#.##..",
+            "#######.#........#.#####.##..#...##..#..#.#",
+            "..........##.......#....#.#.##.#.#..#.#.##.",
+            "##...##.#.####.###...##.###.####.#...##..##",
+            "...#.#..##..##.##..##...##.#....#.###..#...",
+            "##.##.##.#...#..##.#.#....#.#.##..#########",
+            "...###.#####..#######..#.##.#.#..###..#...#",
+            "#..##.###.#....#..#####.##.##.#.###.###.#.#",
+            "#..#.#.######..#..###.##...#...###..###...#",
+            "###.#.#.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#####",
+        ];
+        assert_eq!(rows(15, ErrorCorrectionLevel::H), expected);
+    }
+
+    #[test]
+    fn snapshot_r17x43() {
+        let expected = [
+            "#######.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#.###",
+            "#.....#..#....#.##..#.####.#...#.#..#####.#",
+            "#.###.#...#.#.#....#####..#.#..##.##.####.#",
+            "#.###.#.#...###.#####.##.#####...#....###..",
+            "#.###.#.#..###.#########.###..####.###...##",
+            "#.....#.###.#########.##..#..##.#.##.####..",
+            "#######..##..#..#.#..#.###.#.#...###..#.#.#",
+            ".........##...#.##..#.#.......##..##.....#.",
+            "##.###..#...##.#...###..#.#.##....#.##...##",
+            ".#...#.#.##...#..#..#...##.#...#.#..##.....",
+            "####...#.#.....##..#.##...#.#..##..#.####.#",
+            ".#...####.###.######..##.#####...#.##.####.",
+            "##..##..#####.##########.###..#####.#######",
+            ".##.....##..##...###..##..#..##.#.#####...#",
+            "#.##.###.....#....#.######.#.#...##..##.#.#",
+            "#..#.#..###..#.#.#..#.#.......##...##.#...#",
+            "###.#.#.#.#.#.#.#.#.###.#.#.#.#.#.#.#.#####",
+        ];
+        assert_eq!(rows(17, ErrorCorrectionLevel::M), expected);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::eci::{Charset, encode_eci_designator};
+use crate::qrcode::{Fnc1, QREncoding, QRCodeError, alphanumeric_get_char_code, get_count_bits, get_kanji_sjis, GROUP_SEPARATOR};
+
+// source: https://www.nayuki.io/page/optimal-text-segmentation-for-qr-codes
+
+// a run of characters that is encoded with a single mode indicator and character count
+// the character set is only used by byte mode, every other mode has a fixed character table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub(crate) encoding: QREncoding,
+    pub(crate) data: String,
+    pub(crate) charset: Charset,
+}
+
+impl Segment {
+    pub fn new(encoding: QREncoding, data: String) -> Self {
+        Self { encoding, data, charset: Charset::default() }
+    }
+
+    pub fn new_bytes(data: String, charset: Charset) -> Self {
+        Self { encoding: QREncoding::Byte, data, charset }
+    }
+
+    // ECI header switching the reader to the character set for all following byte segments
+    pub fn eci(charset: Charset) -> Self {
+        Self { encoding: QREncoding::Eci(charset.eci_number()), data: String::new(), charset }
+    }
+
+    // FNC1 header marking the data as GS1 element string or AIM application data
+    pub fn fnc1(fnc1: Fnc1) -> Self {
+        let encoding = match fnc1 {
+            Fnc1::FirstPosition => QREncoding::Fnc1First,
+            Fnc1::SecondPosition(app_indicator) => QREncoding::Fnc1Second(app_indicator),
+        };
+
+        Self::new(encoding, String::new())
+    }
+
+    pub fn encoding(&self) -> &QREncoding {
+        &self.encoding
+    }
+
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    pub fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    // the data converted to the character set of the segment, as written in byte mode
+    pub fn bytes(&self) -> Vec<u8> {
+        self.charset.encode(&self.data).expect("Data can not be represented in the character set!")
+    }
+
+    // the data as a reader reassembles it, byte mode in its character set and Kanji as Shift JIS
+    // header segments carry no data
+    pub fn message_bytes(&self) -> Vec<u8> {
+        match self.encoding {
+            QREncoding::Numeric | QREncoding::AlphaNumeric => self.data.as_bytes().to_vec(),
+            QREncoding::Byte  => self.bytes(),
+            QREncoding::Kanji => self.data.chars()
+                .flat_map(|c| get_kanji_sjis(c).expect("Character can not be encoded in Kanji mode!").to_be_bytes())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // number of characters as counted by the character count indicator of the encoding
+    pub fn char_count(&self) -> usize {
+        match self.encoding {
+            QREncoding::Byte  => self.bytes().len(),
+            QREncoding::Kanji => self.data.chars().count(),
+            _                 => self.data.len(),
+        }
+    }
+
+    // byte data that a reader without ECI support would not read back as plain ASCII
+    fn needs_eci(&self) -> bool {
+        self.encoding == QREncoding::Byte && !(self.data.is_ascii() && self.bytes() == self.data.as_bytes())
+    }
+
+    // number of bits of the encoded characters without mode indicator and character count
+    pub fn data_bits(&self) -> usize {
+        let len = self.char_count();
+        match self.encoding {
+            QREncoding::Numeric      => len / 3 * 10 + [0, 4, 7][len % 3],
+            QREncoding::AlphaNumeric => len / 2 * 11 + len % 2 * 6,
+            QREncoding::Byte         => len * 8,
+            QREncoding::Kanji        => len * 13,
+            QREncoding::Eci(number)  => encode_eci_designator(number).expect("Invalid ECI assignment number!").len(),
+            QREncoding::StructuredAppend { .. } => 16,
+            QREncoding::Fnc1First               => 0,
+            QREncoding::Fnc1Second(_)           => 8,
+        }
+    }
+
+    // number of bits of the whole segment including mode indicator and character count
+    pub fn total_bits(&self, version: usize) -> Result<usize, QRCodeError> {
+        Ok(4 + get_count_bits(&self.encoding, version)? + self.data_bits())
+    }
+}
+
+// the modes considered by the segmenter, in the order used for the cost arrays
+const SEGMENT_MODES: [QREncoding; 4] = [QREncoding::Numeric, QREncoding::AlphaNumeric, QREncoding::Byte, QREncoding::Kanji];
+
+// in FNC1 mode alphanumeric segments write the group separator as % and a literal % as %%
+fn escape_fnc1_alphanumeric(c: char) -> &'static str {
+    match c {
+        GROUP_SEPARATOR => "%",
+        '%'             => "%%",
+        _               => "",
+    }
+}
+
+// cost of encoding the character in the given mode in 1/6 bits, None if the mode can not encode it
+// numeric and alphanumeric pack 3 and 2 characters into 10 and 11 bits, so their costs are fractional
+fn char_cost(c: char, mode: &QREncoding, charset: Charset, fnc1: bool) -> Option<usize> {
+    match mode {
+        QREncoding::Numeric      if c.is_ascii_digit()                  => Some(20),
+        QREncoding::AlphaNumeric if fnc1 && !escape_fnc1_alphanumeric(c).is_empty() => Some(33 * escape_fnc1_alphanumeric(c).len()),
+        QREncoding::AlphaNumeric if alphanumeric_get_char_code(c) != 99 => Some(33),
+        QREncoding::Byte => charset.encode(c.encode_utf8(&mut [0; 4])).map(|bytes| bytes.len() * 8 * 6),
+        QREncoding::Kanji        if get_kanji_sjis(c).is_some()         => Some(78),
+        _                                                               => None,
+    }
+}
+
+// the segments need an ECI header if their byte data is not plain ASCII
+pub fn needs_eci_header(segments: &[Segment]) -> bool {
+    segments.iter().any(|segment| segment.needs_eci())
+}
+
+// splits the data into segments such that the total bit length for the given version is minimal
+// the count indicator widths only change between versions 9/10 and 26/27, so the result holds for the whole group
+// byte mode characters are converted to the given character set, fnc1 enables the group separator escapes
+pub fn optimal_segments(data: &str, version: usize, charset: Charset, fnc1: bool) -> Result<Vec<Segment>, QRCodeError> {
+    let chars: Vec<char> = data.chars().collect();
+    if chars.is_empty() { return Ok(Vec::new()); }
+
+    // cost of starting a new segment: mode indicator and character count
+    let mut head_costs = [0; SEGMENT_MODES.len()];
+    for (i, mode) in SEGMENT_MODES.iter().enumerate() {
+        head_costs[i] = (4 + get_count_bits(mode, version)?) * 6;
+    }
+
+    // char_modes[i][m] is the mode of character i on the cheapest way to end up in mode m after it
+    let mut char_modes: Vec<[Option<usize>; SEGMENT_MODES.len()]> = Vec::with_capacity(chars.len());
+    let mut prev_costs = head_costs;
+
+    for &c in chars.iter() {
+        let mut cur_costs = [usize::MAX; SEGMENT_MODES.len()];
+        let mut cur_modes = [None; SEGMENT_MODES.len()];
+
+        // stay in the current mode
+        for (m, mode) in SEGMENT_MODES.iter().enumerate() {
+            if let Some(cost) = char_cost(c, mode, charset, fnc1) {
+                cur_costs[m] = prev_costs[m] + cost;
+                cur_modes[m] = Some(m);
+            }
+        }
+
+        if cur_modes.iter().all(|mode| mode.is_none()) {
+            return Err(QRCodeError::UnsupportedCharacter);
+        }
+
+        // switch to another mode after this character, rounding the finished segment up to whole bits
+        let (stay_costs, stay_modes) = (cur_costs, cur_modes);
+        for to in 0..SEGMENT_MODES.len() {
+            for from in 0..SEGMENT_MODES.len() {
+                if stay_modes[from].is_none() { continue; }
+
+                let switch_cost = stay_costs[from].div_ceil(6) * 6 + head_costs[to];
+                if cur_modes[to].is_none() || switch_cost < cur_costs[to] {
+                    cur_costs[to] = switch_cost;
+                    cur_modes[to] = Some(from);
+                }
+            }
+        }
+
+        char_modes.push(cur_modes);
+        prev_costs = cur_costs;
+    }
+
+    // walk back from the cheapest final mode to recover the mode of every character
+    let mut cur_mode = (0..SEGMENT_MODES.len())
+        .filter(|&m| char_modes[chars.len() - 1][m].is_some())
+        .min_by_key(|&m| prev_costs[m])
+        .unwrap();
+
+    let mut modes = vec![0; chars.len()];
+    for i in (0..chars.len()).rev() {
+        cur_mode = char_modes[i][cur_mode].unwrap();
+        modes[i] = cur_mode;
+    }
+
+    // merge runs of the same mode into segments
+    let mut segments: Vec<Segment> = Vec::new();
+    for (c, mode) in chars.into_iter().zip(modes) {
+        let encoding = &SEGMENT_MODES[mode];
+        let text = match encoding {
+            QREncoding::AlphaNumeric if fnc1 && !escape_fnc1_alphanumeric(c).is_empty() => escape_fnc1_alphanumeric(c).to_string(),
+            _ => c.to_string(),
+        };
+
+        match segments.last_mut() {
+            Some(segment) if &segment.encoding == encoding => segment.data.push_str(&text),
+            _ => segments.push(Segment { encoding: encoding.clone(), data: text, charset }),
+        }
+    }
+
+    Ok(segments)
+}
@@ -1,224 +1,527 @@
-const SENTINAL_EXP: u32 = 0xFFFFu32;
-const MAX_POLY_LEN: usize = 255;
+use crate::gf256::{Gf, Gf256, GfTables, QR_PRIMITIVE_POLYNOMIAL};
+use crate::poly::Poly;
 
-fn add_exp(a: u32, b: u32) -> u32 {
-    if a == SENTINAL_EXP || b == SENTINAL_EXP { SENTINAL_EXP }
-    else { (a + b) % 255 }
+// source: https://www.thonky.com/qr-code-tutorial/error-correction-coding
+
+#[derive(Debug, PartialEq)]
+pub enum ReedSolomonError {
+    // more than 255 codewords or not more codewords than error correction codewords
+    InvalidLength,
+    // more errors than the error correction codewords can correct, at most half as many as there are of them
+    TooManyErrors,
 }
 
-fn int_to_exponent(value: u32) -> u32 {
-    let int_to_exponent_lookup = vec![
-        SENTINAL_EXP, 0, 1, 25, 2, 50, 26, 198, 3, 223,
-        51, 238, 27, 104, 199, 75, 4, 100, 224, 14,
-        52, 141, 239, 129, 28, 193, 105, 248, 200, 8,
-        76, 113, 5, 138, 101, 47, 225, 36, 15, 33,
-        53, 147, 142, 218, 240, 18, 130, 69, 29, 181,
-        194, 125, 106, 39, 249, 185, 201, 154, 9, 120,
-        77, 228, 114, 166, 6, 191, 139, 98, 102, 221,
-        48, 253, 226, 152, 37, 179, 16, 145, 34, 136,
-        54, 208, 148, 206, 143, 150, 219, 189, 241, 210,
-        19, 92, 131, 56, 70, 64, 30, 66, 182, 163,
-        195, 72, 126, 110, 107, 58, 40, 84, 250, 133,
-        186, 61, 202, 94, 155, 159, 10, 21, 121, 43,
-        78, 212, 229, 172, 115, 243, 167, 87, 7, 112,
-        192, 247, 140, 128, 99, 13, 103, 74, 222, 237,
-        49, 197, 254, 24, 227, 165, 153, 119, 38, 184,
-        180, 124, 17, 68, 146, 217, 35, 32, 137, 46,
-        55, 63, 209, 91, 149, 188, 207, 205, 144, 135,
-        151, 178, 220, 252, 190, 97, 242, 86, 211, 171,
-        20, 42, 93, 158, 132, 60, 57, 83, 71, 109,
-        65, 162, 31, 45, 67, 216, 183, 123, 164, 118,
-        196, 23, 73, 236, 127, 12, 111, 246, 108, 161,
-        59, 82, 41, 157, 85, 170, 251, 96, 134, 177,
-        187, 204, 62, 90, 203, 89, 95, 176, 156, 169,
-        160, 81, 11, 245, 22, 235, 122, 117, 44, 215,
-        79, 174, 213, 233, 230, 231, 173, 232, 116, 214,
-        244, 234, 168, 80, 88, 175
-    ];
-
-    int_to_exponent_lookup[value as usize]
+// product of (x - alpha^i) for i from first_root to first_root + num_codewords - 1
+fn generator_polynomial<const POLY: u16>(num_codewords: usize, first_root: usize) -> Poly<POLY> {
+    (first_root..first_root + num_codewords).fold(Poly::new(vec![Gf::ONE]), |generator, i| {
+        &generator * &Poly::new(vec![Gf::alpha_pow(i), Gf::ONE])
+    })
 }
 
-fn get_power_of_two(exp: u32) -> u32 {
-    let power_of_two_lookup : Vec<u32> = vec![
-        1, 2, 4, 8, 16, 32, 64, 128, 29, 58,
-        116, 232, 205, 135, 19, 38, 76, 152, 45, 90,
-        180, 117, 234, 201, 143, 3, 6, 12, 24, 48,
-        96, 192, 157, 39, 78, 156, 37, 74, 148, 53,
-        106, 212, 181, 119, 238, 193, 159, 35, 70, 140,
-        5, 10, 20, 40, 80, 160, 93, 186, 105, 210,
-        185, 111, 222, 161, 95, 190, 97, 194, 153, 47,
-        94, 188, 101, 202, 137, 15, 30, 60, 120, 240,
-        253, 231, 211, 187, 107, 214, 177, 127, 254, 225,
-        223, 163, 91, 182, 113, 226, 217, 175, 67, 134,
-        17, 34, 68, 136, 13, 26, 52, 104, 208, 189,
-        103, 206, 129, 31, 62, 124, 248, 237, 199, 147,
-        59, 118, 236, 197, 151, 51, 102, 204, 133, 23,
-        46, 92, 184, 109, 218, 169, 79, 158, 33, 66,
-        132, 21, 42, 84, 168, 77, 154, 41, 82, 164,
-        85, 170, 73, 146, 57, 114, 228, 213, 183, 115,
-        230, 209, 191, 99, 198, 145, 63, 126, 252, 229,
-        215, 179, 123, 246, 241, 255, 227, 219, 171, 75,
-        150, 49, 98, 196, 149, 55, 110, 220, 165, 87,
-        174, 65, 130, 25, 50, 100, 200, 141, 7, 14,
-        28, 56, 112, 224, 221, 167, 83, 166, 81, 162,
-        89, 178, 121, 242, 249, 239, 195, 155, 43, 86,
-        172, 69, 138, 9, 18, 36, 72, 144, 61, 122,
-        244, 245, 247, 243, 251, 235, 203, 139, 11, 22,
-        44, 88, 176, 125, 250, 233, 207, 131, 27, 54,
-        108, 216, 173, 71, 142, 1
-    ];
-
-    power_of_two_lookup[exp as usize]
+// the most error correction codewords per block of any QR code, micro or rMQR symbol
+const MAX_QR_EC_LEN: usize = 30;
+
+// coefficients of the QR generator polynomials for 0 to 30 error correction codewords, lowest power first
+// the leading coefficient 1 of x^num_codewords is left out
+static QR_GENERATORS: [[Gf256; MAX_QR_EC_LEN]; MAX_QR_EC_LEN + 1] = gen_qr_generators();
+
+// builds every generator from the previous one by multiplying it with (x - alpha^(len - 1))
+const fn gen_qr_generators() -> [[Gf256; MAX_QR_EC_LEN]; MAX_QR_EC_LEN + 1] {
+    let tables = GfTables::new(QR_PRIMITIVE_POLYNOMIAL);
+    let mut generators = [[Gf(0); MAX_QR_EC_LEN]; MAX_QR_EC_LEN + 1];
+
+    let mut len = 1;
+    while len <= MAX_QR_EC_LEN {
+        let previous = generators[len - 1];
+        let root = tables.alpha_pow(len - 1);
+
+        let mut i = 0;
+        while i < len {
+            let coeff = if i == len - 1 { 1 } else { previous[i].0 };
+            let shifted = if i == 0 { 0 } else { previous[i - 1].0 };
+            generators[len][i] = Gf(shifted ^ tables.mul(coeff, root));
+            i += 1;
+        }
+        len += 1;
+    }
+
+    generators
 }
 
-/*
-    Implement look up table for new exponents of coefficients
-*/
-pub fn add_coeffs(coeff_power_a: u32, coeff_power_b: u32) -> u32 {
-    if coeff_power_a == 0xFFFF {
-        coeff_power_b
-    } else if coeff_power_b == 0xFFFF {
-        coeff_power_a
-    } else {
-        let mut sum = get_power_of_two(coeff_power_a) ^ get_power_of_two(coeff_power_b);
-        if sum > 255 {
-            sum ^= 285;
-        }
-        int_to_exponent(sum)
+// product of (x - alpha^i) for i from 0 to num_codewords - 1, the generator of QR code symbols
+pub fn get_generator_polynomial(num_codewords: usize) -> Poly {
+    match QR_GENERATORS.get(num_codewords) {
+        Some(coeffs) => {
+            let mut coeffs = coeffs[..num_codewords].to_vec();
+            coeffs.push(Gf::ONE);
+            Poly::new(coeffs)
+        },
+        None => generator_polynomial(num_codewords, 0),
     }
 }
 
-// there can be at most 255 error correction code words
-// num_codewords has to be >= 7
-pub fn get_generator_polynomial(num_codewords: u32) -> [u32; MAX_POLY_LEN] {
-    // sentinal value for 0 coeff == 0xFFFF
-    let mut coeff_powers = [SENTINAL_EXP; MAX_POLY_LEN];
-
-    // first polynomial => alpha^0 * x^2 + alpha^25 * x^1 + alpha^0 * x^0
-    coeff_powers[0] = 0;
-    coeff_powers[1] = 0;
-
-    // in each step multiply by (x - alpha^i), i also represents the currently highest power of x
-    for i in 1..=(num_codewords - 1) as usize {
-        // shift current codewords one to the left and add the current codeword multiplied by alpha^i
-        let mut new_powers = [SENTINAL_EXP; MAX_POLY_LEN];
-        for j in 0..=(i + 1) as usize {
-            if j == i + 1 {
-                // alpha_j_n = 0
-                new_powers[j] = 0;
-            } else {
-                // alpha_j_n = alpha_j_n-1 * alpha^i + alpha_j-1_n-1
-                let value1 = add_exp(coeff_powers[j], i as u32);
-                let value2 = if j == 0 { 0xFFFF }
-                             else      { coeff_powers[j - 1] };
-
-                new_powers[j] = add_coeffs(value1, value2);
-            }
+// remainder of the data times x^ec_len divided by a generator with ec_len coefficients below its leading 1,
+// computed by shifting the data through a register of ec_len codewords, highest power first
+fn lfsr_remainder<const POLY: u16>(data: &[u8], generator: &[Gf<POLY>]) -> Vec<u8> {
+    let ec_len = generator.len();
+    if ec_len == 0 { return Vec::new(); }
+
+    let mut register = vec![Gf::ZERO; ec_len];
+    for &codeword in data {
+        let factor = Gf::from(codeword) + register[0];
+        register.rotate_left(1);
+        register[ec_len - 1] = Gf::ZERO;
+        if factor.is_zero() { continue; }
+
+        for (i, slot) in register.iter_mut().enumerate() {
+            *slot += generator[ec_len - 1 - i] * factor;
         }
-
-        coeff_powers = new_powers;
     }
 
-    return coeff_powers;
+    register.into_iter().map(u8::from).collect()
 }
 
-pub fn mult_by_pow_of_x(pow: u32, coeffs: &mut [u32]) {
-    let mut new_coeffs = [SENTINAL_EXP; MAX_POLY_LEN];
+// Reed-Solomon code over GF(2^8) with ec_len error correction codewords, the roots of the generator polynomial
+// are the ec_len consecutive powers of alpha starting at alpha^first_root
+// QR codes use the polynomial 285 and the first root 0, Data Matrix the polynomial 301 and the first root 1
+#[derive(Debug, Clone)]
+pub struct ReedSolomon<const POLY: u16 = QR_PRIMITIVE_POLYNOMIAL> {
+    ec_len: usize,
+    first_root: usize,
+    generator: Poly<POLY>,
+}
+
+impl ReedSolomon {
+    // the code of QR code symbols, its generator is taken from the precomputed ones if possible
+    pub fn new(ec_len: usize) -> Self {
+        Self { ec_len, first_root: 0, generator: get_generator_polynomial(ec_len) }
+    }
+}
+
+impl<const POLY: u16> ReedSolomon<POLY> {
+    // the first root alpha^0 in the field of the type, like ReedSolomon::<301>::new_in_field(ec_len)
+    pub fn new_in_field(ec_len: usize) -> Self {
+        Self::with_first_root(ec_len, 0)
+    }
+
+    // like ReedSolomon::<301>::with_first_root(ec_len, 1) for Data Matrix
+    pub fn with_first_root(ec_len: usize, first_root: usize) -> Self {
+        Self { ec_len, first_root, generator: generator_polynomial(ec_len, first_root) }
+    }
+
+    pub fn ec_len(&self) -> usize {
+        self.ec_len
+    }
+
+    pub fn first_root(&self) -> usize {
+        self.first_root
+    }
+
+    pub fn generator(&self) -> &Poly<POLY> {
+        &self.generator
+    }
+
+    // remainder of the message times x^ec_len divided by the generator polynomial, highest power first
+    // a remainder with a lower degree is padded with leading zero codewords
+    pub fn ec_codewords(&self, data: &[u8]) -> Vec<u8> {
+        lfsr_remainder(data, &self.generator.coeffs()[..self.ec_len])
+    }
+
+    // the data followed by its error correction codewords
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut block = data.to_vec();
+        block.extend(self.ec_codewords(data));
+        block
+    }
+
+    // corrects up to ec_len / 2 wrong codewords of a block from encode in place and returns how many were wrong
+    // the block is left unchanged if it can not be corrected
+    pub fn decode(&self, block: &mut [u8]) -> Result<usize, ReedSolomonError> {
+        self.decode_with_erasures(block, &[])
+    }
+
+    // like decode, but the codewords at the indices in erasures are known to be unreadable
+    // every erasure uses up one error correction codeword instead of two, so e errors and f erasures can be
+    // corrected as long as 2e + f <= ec_len
+    pub fn decode_with_erasures(&self, block: &mut [u8], erasures: &[usize]) -> Result<usize, ReedSolomonError> {
+        let len = block.len();
+        if len > 255 || len <= self.ec_len || erasures.iter().any(|&i| i >= len) {
+            return Err(ReedSolomonError::InvalidLength);
+        }
+
+        let mut erasures = erasures.to_vec();
+        erasures.sort_unstable();
+        erasures.dedup();
+        if erasures.len() > self.ec_len {
+            return Err(ReedSolomonError::TooManyErrors);
+        }
+
+        let syndromes = self.syndromes(block);
+        if syndromes.iter().all(|syndrome| syndrome.is_zero()) {
+            return Ok(0);
+        }
 
-    for exp in (0..coeffs.len()).rev() {
-        let new_exp = add_exp(exp as u32, pow) as usize;
-        new_coeffs[new_exp] = coeffs[exp];
+        // the Forney syndromes S(x) * Gamma(x) from x^f on no longer depend on the erasures,
+        // so Berlekamp-Massey only has to find the locator of the unknown errors
+        // source: https://en.wikipedia.org/wiki/Forney_algorithm#Erasures
+        let erasure_locator = get_erasure_locator(&erasures, len);
+        let syndrome_poly = &Poly::new(syndromes.clone()) * &erasure_locator;
+        let forney_syndromes: Vec<Gf<POLY>> = (erasures.len()..self.ec_len).map(|k| syndrome_poly.coeff(k)).collect();
+
+        let (error_locator, errors) = berlekamp_massey(&forney_syndromes);
+        if 2 * errors + erasures.len() > self.ec_len || error_locator.degree() != Some(errors) {
+            return Err(ReedSolomonError::TooManyErrors);
+        }
+
+        // a locator without one root per error points to locations outside of the block
+        let locator = &error_locator * &erasure_locator;
+        let positions = find_error_positions(&locator, len);
+        if positions.len() != errors + erasures.len() {
+            return Err(ReedSolomonError::TooManyErrors);
+        }
+
+        let mut corrected = block.to_vec();
+        for (&i, magnitude) in positions.iter().zip(self.error_magnitudes(&syndromes, &locator, &positions, len)) {
+            corrected[i] ^= magnitude.0;
+        }
+
+        // too many errors can also lead to a valid looking locator that does not give a codeword
+        if self.syndromes(&corrected).iter().any(|syndrome| !syndrome.is_zero()) {
+            return Err(ReedSolomonError::TooManyErrors);
+        }
+
+        let wrong = block.iter().zip(&corrected).filter(|(received, fixed)| received != fixed).count();
+        block.copy_from_slice(&corrected);
+        Ok(wrong)
     }
 
-    coeffs.copy_from_slice(&new_coeffs[..]);
+    // S_j = r(alpha^(first_root + j)) for every root of the generator polynomial, all zero if the block is valid
+    fn syndromes(&self, block: &[u8]) -> Vec<Gf<POLY>> {
+        let received = Poly::from_codewords(block);
+        (0..self.ec_len).map(|j| received.eval(Gf::alpha_pow(self.first_root + j))).collect()
+    }
+
+    // Forney: the magnitude of the error at X is X^(1 - first_root) * Omega(X^-1) / Lambda'(X^-1), with the evaluator
+    // Omega(x) = S(x) * Lambda(x) mod x^ec_len
+    fn error_magnitudes(&self, syndromes: &[Gf<POLY>], locator: &Poly<POLY>, positions: &[usize], len: usize) -> Vec<Gf<POLY>> {
+        let (_, evaluator) = (&Poly::new(syndromes.to_vec()) * locator).divmod(&Poly::monomial(Gf::ONE, syndromes.len()));
+        let derivative = locator.derivative();
+
+        positions.iter().map(|&i| {
+            let power = len - 1 - i;
+            let location_inv = Gf::alpha_pow(255 - power % 255);
+            let factor = Gf::alpha_pow(power * (256 - self.first_root % 255));
+            factor * evaluator.eval(location_inv) / derivative.eval(location_inv)
+        }).collect()
+    }
 }
 
-// for debugging purposes
-pub fn pretty_print_polynomial_pows(exponents: &[u32]) {
-    for idx in (0..exponents.len()).rev() {
-        let exp = exponents[idx];
-        if exp != SENTINAL_EXP {
-            if idx != 0 {
-                print!("α^{} * x^{} + ", exp, idx);
-            } else {
-                print!("α^{}", exp);
-            }
+// shortest linear feedback shift register that generates the syndromes, its connection polynomial is the
+// error locator Lambda(x) = product of (1 - X_k x) over the error locations X_k
+// source: https://en.wikipedia.org/wiki/Berlekamp%E2%80%93Massey_algorithm
+fn berlekamp_massey<const POLY: u16>(syndromes: &[Gf<POLY>]) -> (Poly<POLY>, usize) {
+    let mut locator = Poly::new(vec![Gf::ONE]);
+    let mut previous = Poly::new(vec![Gf::ONE]);
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = Gf::ONE;
+
+    for k in 0..syndromes.len() {
+        let discrepancy = (1..=errors).fold(syndromes[k], |sum, i| sum + locator.coeff(i) * syndromes[k - i]);
+        if discrepancy.is_zero() {
+            shift += 1;
+            continue;
+        }
+
+        let updated = &locator - &previous.scale(discrepancy / previous_discrepancy).shift(shift);
+        if 2 * errors <= k {
+            previous = std::mem::replace(&mut locator, updated);
+            errors = k + 1 - errors;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
         }
     }
 
-    println!("");
+    (locator, errors)
 }
 
-pub fn pretty_print_polynomial_ints(exponents: &[u32]) {
-    for idx in (0..exponents.len()).rev() {
-        let exp = exponents[idx];
-        if exp != SENTINAL_EXP {
-            if idx != 0 {
-                print!("{} * x^{} + ", get_power_of_two(exp), idx);
-            } else {
-                print!("{}", get_power_of_two(exp));
+// Chien search: the codeword at index i has the location X = alpha^(len - 1 - i), it is wrong if Lambda(X^-1) = 0
+fn find_error_positions<const POLY: u16>(locator: &Poly<POLY>, len: usize) -> Vec<usize> {
+    (0..len).filter(|&i| locator.eval(Gf::alpha_pow(255 - (len - 1 - i) % 255)).is_zero()).collect()
+}
+
+// Gamma(x) = product of (1 - Y x) over the locations Y of the erased codewords
+fn get_erasure_locator<const POLY: u16>(erasures: &[usize], len: usize) -> Poly<POLY> {
+    erasures.iter().fold(Poly::new(vec![Gf::ONE]), |locator, &i| {
+        &locator * &Poly::new(vec![Gf::ONE, Gf::alpha_pow(len - 1 - i)])
+    })
+}
+
+// corrects up to num_codewords / 2 wrong codewords of a QR code block of data followed by its error correction
+// codewords and returns the data codewords
+pub fn correct_errors(codewords: &[u8], num_codewords: usize) -> Result<Vec<u8>, ReedSolomonError> {
+    correct_errors_and_erasures(codewords, num_codewords, &[])
+}
+
+// like correct_errors, with the indices of codewords that are known to be unreadable
+pub fn correct_errors_and_erasures(codewords: &[u8], num_codewords: usize, erasures: &[usize]) -> Result<Vec<u8>, ReedSolomonError> {
+    let mut block = codewords.to_vec();
+    ReedSolomon::new(num_codewords).decode_with_erasures(&mut block, erasures)?;
+
+    block.truncate(codewords.len() - num_codewords);
+    Ok(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // small xorshift generator, so every run corrupts the same codewords
+    struct Rng(u32);
+
+    impl Rng {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+
+        fn byte(&mut self) -> u8 {
+            self.next() as u8
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            self.next() as usize % n
+        }
+
+        // count different indices below n
+        fn positions(&mut self, count: usize, n: usize) -> Vec<usize> {
+            let mut positions = Vec::with_capacity(count);
+            while positions.len() < count {
+                let i = self.below(n);
+                if !positions.contains(&i) { positions.push(i); }
             }
+            positions
         }
     }
 
-    println!("");
-}
+    fn random_block(rng: &mut Rng, data_len: usize, ec_len: usize) -> Vec<u8> {
+        let data: Vec<u8> = (0..data_len).map(|_| rng.byte()).collect();
+        ReedSolomon::new(ec_len).encode(&data)
+    }
+
+    // xors a nonzero value into every codeword at the positions
+    fn corrupt(rng: &mut Rng, block: &mut [u8], positions: &[usize]) {
+        for &i in positions {
+            block[i] ^= rng.byte().max(1);
+        }
+    }
 
+    #[test]
+    fn berlekamp_massey_finds_the_error_locations() {
+        let mut block = ReedSolomon::new(10).encode(b"Reed-Solomon");
+        block[3] ^= 0x55;
+        block[17] ^= 0x01;
 
-pub fn get_poly_degree(coeffs: &[u32]) -> u32 {
-    for i in (0..MAX_POLY_LEN).rev() {
-        if coeffs[i] != SENTINAL_EXP { return i as u32; }
+        let (locator, errors) = berlekamp_massey(&ReedSolomon::new(10).syndromes(&block));
+        assert_eq!(errors, 2);
+        assert_eq!(locator.degree(), Some(2));
+        assert_eq!(find_error_positions(&locator, block.len()), [3, 17]);
     }
 
-    0
-}
+    #[test]
+    fn corrects_up_to_half_of_the_ec_codewords() {
+        let mut rng = Rng(0x1234_5678);
+        for (data_len, ec_len) in [(19, 7), (16, 10), (13, 13), (9, 17), (34, 22), (118, 30), (225, 30)] {
+            for errors in 0..=ec_len / 2 {
+                for _ in 0..20 {
+                    let original = random_block(&mut rng, data_len, ec_len);
+                    let mut block = original.clone();
+                    let positions = rng.positions(errors, original.len());
+                    corrupt(&mut rng, &mut block, &positions);
+
+                    assert_eq!(correct_errors(&block, ec_len), Ok(original[..data_len].to_vec()));
+                    assert_eq!(ReedSolomon::new(ec_len).decode(&mut block), Ok(errors));
+                    assert_eq!(block, original);
+                }
+            }
+        }
+    }
 
-pub fn nums_to_coeffs(nums: &[u8]) -> [u32; 255] {
-    // sentinal value for 0 coeff == 0xFFFF
-    let mut coeff_powers = [SENTINAL_EXP; 255];
-    
+    // with an odd number of ec codewords a block with one error too many is still more than ec_len / 2 codewords away
+    // from every other valid block, so it can never be corrected into a wrong one
+    // with an even number it may lie exactly ec_len / 2 + 1 codewords away from another valid block and be corrected
+    // into that one, so only the original block must never come back
+    #[test]
+    fn one_error_too_many_is_reported() {
+        let mut rng = Rng(0x9e37_79b9);
+        for (data_len, ec_len) in [(19, 7), (13, 13), (9, 17), (30, 15), (16, 10), (46, 26)] {
+            for _ in 0..200 {
+                let original = random_block(&mut rng, data_len, ec_len);
+                let mut block = original.clone();
+                let positions = rng.positions(ec_len / 2 + 1, original.len());
+                corrupt(&mut rng, &mut block, &positions);
+
+                let received = block.clone();
+                let result = ReedSolomon::new(ec_len).decode(&mut block);
+                if ec_len % 2 == 1 {
+                    assert_eq!(result, Err(ReedSolomonError::TooManyErrors));
+                    assert_eq!(correct_errors(&received, ec_len), Err(ReedSolomonError::TooManyErrors));
+                } else {
+                    assert_ne!(block, original);
+                    assert_ne!(correct_errors(&received, ec_len), Ok(original[..data_len].to_vec()));
+                }
+                if result.is_err() { assert_eq!(block, received); }
+            }
+        }
+    }
 
-    for i in 0..nums.len() {
-        coeff_powers[i] = int_to_exponent(nums[nums.len() - i - 1] as u32);
+    #[test]
+    fn invalid_block_lengths_are_rejected() {
+        assert_eq!(correct_errors(&[0; 10], 10), Err(ReedSolomonError::InvalidLength));
+        assert_eq!(correct_errors(&[0; 256], 10), Err(ReedSolomonError::InvalidLength));
+        assert_eq!(correct_errors(&[0; 255], 10), Ok(vec![0; 245]));
     }
 
-    coeff_powers
-}
+    #[test]
+    fn corrects_erasures_and_errors_within_capacity() {
+        let mut rng = Rng(0x0bad_cafe);
+        for (data_len, ec_len) in [(19, 7), (16, 10), (9, 17), (34, 22), (118, 30)] {
+            for erasures in 0..=ec_len {
+                for errors in 0..=(ec_len - erasures) / 2 {
+                    let original = random_block(&mut rng, data_len, ec_len);
+                    let positions = rng.positions(erasures + errors, original.len());
+                    let (erased, wrong) = positions.split_at(erasures);
+
+                    // an erased codeword is unreadable, it may also happen to hold the right value
+                    let mut block = original.clone();
+                    for &i in erased { block[i] = rng.byte(); }
+                    corrupt(&mut rng, &mut block, wrong);
+                    let changed = block.iter().zip(&original).filter(|(a, b)| a != b).count();
+
+                    assert_eq!(correct_errors_and_erasures(&block, ec_len, erased), Ok(original[..data_len].to_vec()));
+                    assert_eq!(ReedSolomon::new(ec_len).decode_with_erasures(&mut block, erased), Ok(changed));
+                    assert_eq!(block, original);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_erasures_count_once() {
+        let original = ReedSolomon::new(4).encode(b"erasure");
+        let mut block = original.clone();
+        block[0] ^= 1;
+        block[5] ^= 2;
 
-pub fn get_code_words(msg_coeffs_pows: &mut [u32], num_codewords: u32) -> Vec<u32> {
-    mult_by_pow_of_x(num_codewords, &mut msg_coeffs_pows[..]);
-    
-    let gen_coeff_pows = get_generator_polynomial(num_codewords);
-    let mut curr_msg_deg = get_poly_degree(msg_coeffs_pows);
-    let gen_deg = num_codewords;
-    let num_iterations = curr_msg_deg - gen_deg;
+        assert_eq!(ReedSolomon::new(4).decode_with_erasures(&mut block, &[5, 0, 5, 0, 5]), Ok(2));
+        assert_eq!(block, original);
+    }
+
+    #[test]
+    fn erasure_outside_of_the_block_is_rejected() {
+        let mut block = ReedSolomon::new(10).encode(b"0123456789");
+        let received = block.clone();
+
+        assert_eq!(ReedSolomon::new(10).decode_with_erasures(&mut block, &[3, 20]), Err(ReedSolomonError::InvalidLength));
+        assert_eq!(correct_errors_and_erasures(&block, 10, &[255]), Err(ReedSolomonError::InvalidLength));
+        assert_eq!(block, received);
+    }
+
+    #[test]
+    fn more_erasures_than_ec_codewords_are_rejected() {
+        let mut block = ReedSolomon::new(10).encode(b"0123456789");
+        let received = block.clone();
+        let erasures: Vec<usize> = (0..11).collect();
 
-    let mut temp_gen_coeff_pows = [0xFFFFu32; MAX_POLY_LEN];
+        assert_eq!(ReedSolomon::new(10).decode_with_erasures(&mut block, &erasures), Err(ReedSolomonError::TooManyErrors));
+        assert_eq!(block, received);
+    }
 
-    // perform the long division
-    for _ in 0..num_iterations+1 {
-        // make degree of gen poly and msg poly the same
-        temp_gen_coeff_pows[..].copy_from_slice(&gen_coeff_pows[..]);
-        mult_by_pow_of_x((curr_msg_deg - gen_deg) as u32, &mut temp_gen_coeff_pows[..]);
+    // like one_error_too_many_is_reported, an odd number of ec codewords left after the erasures guarantees that
+    // one error more than they can correct is detected
+    #[test]
+    fn erasures_and_one_error_too_many_are_reported() {
+        let mut rng = Rng(0x2545_f491);
+        for (data_len, ec_len) in [(19, 7), (9, 17), (30, 15)] {
+            for erasures in (0..ec_len).step_by(2) {
+                for _ in 0..20 {
+                    let original = random_block(&mut rng, data_len, ec_len);
+                    let positions = rng.positions(erasures + (ec_len - erasures) / 2 + 1, original.len());
+
+                    let mut block = original.clone();
+                    corrupt(&mut rng, &mut block, &positions);
+                    let received = block.clone();
+
+                    let result = ReedSolomon::new(ec_len).decode_with_erasures(&mut block, &positions[..erasures]);
+                    assert_eq!(result, Err(ReedSolomonError::TooManyErrors));
+                    assert_eq!(block, received);
+                }
+            }
+        }
+    }
 
-        // multiply gen poly be leading coefficient of the msg poly
-        let leading = msg_coeffs_pows[curr_msg_deg as usize];
-        for i in 0..MAX_POLY_LEN {
-            temp_gen_coeff_pows[i] = add_exp(temp_gen_coeff_pows[i], leading);
+    // corrects every number of errors and erasures the code can handle in a few random blocks
+    fn assert_round_trip<const POLY: u16>(code: &ReedSolomon<POLY>, data_len: usize, rng: &mut Rng) {
+        let ec_len = code.ec_len();
+        for erasures in 0..=ec_len {
+            let errors = (ec_len - erasures) / 2;
+            let data: Vec<u8> = (0..data_len).map(|_| rng.byte()).collect();
+            let original = code.encode(&data);
+            assert_eq!(original[..data_len], data[..]);
+
+            let positions = rng.positions(erasures + errors, original.len());
+            let mut block = original.clone();
+            corrupt(rng, &mut block, &positions);
+
+            assert!(code.decode_with_erasures(&mut block, &positions[..erasures]).is_ok());
+            assert_eq!(block, original);
         }
+    }
+
+    // source: ISO/IEC 16022, the encoding example of "123456" in a 10x10 Data Matrix symbol
+    #[test]
+    fn data_matrix_reference_codewords() {
+        let code = ReedSolomon::<301>::with_first_root(5, 1);
+        assert_eq!(code.ec_codewords(&[142, 164, 186]), [114, 25, 5, 88, 102]);
+        assert_eq!(code.encode(&[142, 164, 186]), [142, 164, 186, 114, 25, 5, 88, 102]);
+    }
 
-        // xor the coefficients
-        for i in 0..MAX_POLY_LEN {
-            msg_coeffs_pows[i] = add_coeffs(msg_coeffs_pows[i], temp_gen_coeff_pows[i]);
+    #[test]
+    fn data_matrix_round_trip() {
+        let mut rng = Rng(0x0301_0001);
+        for (data_len, ec_len) in [(3, 5), (8, 10), (18, 14), (62, 36), (156, 68)] {
+            assert_round_trip(&ReedSolomon::<301>::with_first_root(ec_len, 1), data_len, &mut rng);
         }
+    }
+
+    #[test]
+    fn other_field_with_first_root_zero() {
+        let code = ReedSolomon::<301>::new_in_field(12);
+        assert_eq!(code.first_root(), 0);
+        assert_eq!(code.generator(), ReedSolomon::<301>::with_first_root(12, 0).generator());
+        assert_round_trip(&code, 40, &mut Rng(0x0301_0000));
+    }
 
-        curr_msg_deg -= 1;
+    #[test]
+    fn non_zero_first_roots() {
+        let mut rng = Rng(0x5eed_0f0f);
+        for first_root in [1, 2, 112, 120, 254] {
+            let code = ReedSolomon::<QR_PRIMITIVE_POLYNOMIAL>::with_first_root(16, first_root);
+            assert_eq!(code.generator().degree(), Some(16));
+            assert_round_trip(&code, 50, &mut rng);
+        }
     }
 
-    let mut codewords = Vec::with_capacity(curr_msg_deg as usize);
-    for i in (0..curr_msg_deg+1).rev() {
-        codewords.push(get_power_of_two(msg_coeffs_pows[i as usize]));
+    #[test]
+    fn precomputed_generators_match_computed_ones() {
+        for ec_len in 0..=MAX_QR_EC_LEN + 5 {
+            assert_eq!(ReedSolomon::new(ec_len).generator(), &generator_polynomial(ec_len, 0));
+            assert_eq!(&get_generator_polynomial(ec_len), ReedSolomon::new(ec_len).generator());
+        }
     }
-    codewords
-}
\ No newline at end of file
+}
@@ -13,6 +13,6 @@ with open("versions.txt", "r") as f:
         if i % 5 == 4: H.append(get_elems(line))
 
     for errorCorrection, name in [(L, "L"), (M, "M"), (Q, "Q"), (H, "H")]:
-        for j in range(0, 3):
-            encoding = ["Numeric", "AlphaNumeric", "Byte"][j]
-            print(f"(QREncoding::{encoding}, ErrorCorrectionLevel::{name}, vec![{','.join([errorCorrection[i][j] for i in range(len(errorCorrection))])}]),")
\ No newline at end of file
+        for j in range(0, 4):
+            encoding = ["Numeric", "AlphaNumeric", "Byte", "Kanji"][j]
+            print(f"(QREncoding::{encoding}, ErrorCorrectionLevel::{name}, [{','.join([errorCorrection[i][j] for i in range(len(errorCorrection))])}]),")
\ No newline at end of file